@@ -1,14 +1,14 @@
 use crate::layout;
 use crate::support;
 
-use glium;
 use glium::glutin;
 
 use crate::image_util;
 use crate::image_util::ImageId;
+use crate::notify::Notifications;
 use overwatch::Hero;
 use std::collections::BTreeMap;
-use match_history::MatchDb;
+use std::error::Error;
 
 pub struct App {
     pub ui: conrod_core::Ui,
@@ -17,40 +17,65 @@ pub struct App {
     pub images: image_util::ImageMap,
     pub renderer: conrod_glium::Renderer,
     pub assets: AppAssets,
+    pub notifications: Notifications,
 }
 
 pub struct AppAssets {
     pub portraits: BTreeMap<Hero, ImageId>,
+    pub missing_portrait: ImageId,
+}
+
+impl AppAssets {
+    /// The hero's portrait, or a placeholder if it could not be loaded.
+    pub fn portrait(&self, hero: Hero) -> ImageId {
+        self.portraits
+            .get(&hero)
+            .copied()
+            .unwrap_or(self.missing_portrait)
+    }
+
+    pub fn has_portrait(&self, hero: Hero) -> bool {
+        self.portraits.contains_key(&hero)
+    }
 }
 
 impl App {
-    pub fn new(window: support::GliumDisplayWinitWrapper, _events: &glutin::EventsLoop) -> Self {
+    pub fn new(
+        window: support::GliumDisplayWinitWrapper,
+        _events: &glutin::EventsLoop,
+    ) -> Result<Self, Box<dyn Error>> {
         let window_dimm = window
             .0
             .gl_window()
             .window()
             .get_inner_size()
-            .expect("expected getting window size to succeed.");
+            .ok_or("the window closed while starting up")?;
 
         // Create UI.
         let mut ui = conrod_core::UiBuilder::new([window_dimm.width, window_dimm.height]).build();
-        let renderer = conrod_glium::Renderer::new(&window.0)
-            .expect("expected loading conrod glium renderer to succeed.");
+        let renderer = conrod_glium::Renderer::new(&window.0)?;
 
-        // The image map describing each of our widget->image mappings (in our case, none).
+        // The image map describing each of our widget->image mappings.
         let mut image_map = image_util::ImageMap::new();
+        let mut notifications = Notifications::default();
 
-        let portraits = image_util::load_ow_portraits(&mut image_map, &window.0);
+        let portraits =
+            image_util::load_ow_portraits(&mut image_map, &window.0, &mut notifications);
+        let missing_portrait = image_map.insert(image_util::placeholder_image(&window.0)?);
 
         let ids = layout::Ids::new(&mut ui.widget_id_generator());
 
-        App {
-            ui: ui,
+        Ok(App {
+            ui,
             display: window,
-            ids: ids,
+            ids,
             images: image_map,
-            renderer: renderer,
-            assets: AppAssets { portraits },
-        }
+            renderer,
+            assets: AppAssets {
+                portraits,
+                missing_portrait,
+            },
+            notifications,
+        })
     }
 }
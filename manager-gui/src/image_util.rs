@@ -1,46 +1,75 @@
 pub type ImageMap = conrod_core::image::Map<glium::texture::Texture2d>;
 pub type ImageId = conrod_core::image::Id;
 
+use crate::notify::Notifications;
 use overwatch::Hero;
 use std::collections::BTreeMap;
+use std::error::Error;
 
 const OVERWATCH_PORTRAITS: &str = "images/overwatch/portraits/";
 
+/// Load every hero's portrait, leaving out the ones that can't be read.
+///
+/// Missing portraits are reported to `notifications` rather than stopping the app,
+/// the layouts draw a placeholder in their place.
 pub fn load_ow_portraits(
     image_map: &mut ImageMap,
     display: &glium::Display,
+    notifications: &mut Notifications,
 ) -> BTreeMap<Hero, ImageId> {
-    let assets = find_folder::Search::KidsThenParents(3, 5)
-        .for_folder("assets")
-        .unwrap();
+    let assets = match find_folder::Search::KidsThenParents(3, 5).for_folder("assets") {
+        Ok(assets) => assets,
+        Err(e) => {
+            error!("could not find the assets folder: {}", e);
+            notifications.warn("could not find the assets folder, hero portraits are missing");
+            return BTreeMap::new();
+        },
+    };
 
     let portrait_dir = assets.join(OVERWATCH_PORTRAITS);
-    overwatch::Hero::iter()
-        .map(|h| {
-            let mut hero_portrait = portrait_dir.clone();
-            hero_portrait.push(h.blizzard_name());
-            hero_portrait.set_extension("png");
-            //let img = conrod_core::image::open(hero_portrait).unwrap();
-            let img = load_image(display, hero_portrait);
-            let id = image_map.insert(img);
-            (h, id)
-        })
-        .collect()
+    let mut portraits = BTreeMap::new();
+    let mut missing = Vec::new();
+    for h in overwatch::Hero::iter() {
+        let mut hero_portrait = portrait_dir.clone();
+        hero_portrait.push(h.asset_name());
+        hero_portrait.set_extension("png");
+        match load_image(display, &hero_portrait) {
+            Ok(img) => {
+                portraits.insert(h, image_map.insert(img));
+            },
+            Err(e) => {
+                warn!("could not load {}: {}", hero_portrait.display(), e);
+                missing.push(h.to_string());
+            },
+        }
+    }
+    if !missing.is_empty() {
+        notifications.warn(format!("no portrait for {}", missing.join(", ")));
+    }
+    portraits
+}
+
+/// A plain grey square to draw where a portrait is missing.
+pub fn placeholder_image(
+    display: &glium::Display,
+) -> Result<glium::texture::Texture2d, glium::texture::TextureCreationError> {
+    let raw_image = glium::texture::RawImage2d::from_raw_rgba(vec![96u8, 96, 96, 255], (1, 1));
+    glium::texture::Texture2d::new(display, raw_image)
 }
 
 // Load an image from our assets folder as a texture we can draw to the screen.
-fn load_image<P>(display: &glium::Display, path: P) -> glium::texture::Texture2d
+fn load_image<P>(display: &glium::Display, path: P) -> Result<glium::texture::Texture2d, Box<dyn Error>>
 where
     P: AsRef<std::path::Path>,
 {
     let path = path.as_ref();
-    let rgba_image = image::open(&std::path::Path::new(&path)).unwrap().to_rgba();
+    let rgba_image = image::open(std::path::Path::new(&path))?.to_rgba();
     let image_dimensions = rgba_image.dimensions();
     let raw_image = glium::texture::RawImage2d::from_raw_rgba_reversed(
         &rgba_image.into_raw(),
         image_dimensions,
     );
     //let texture = glium::texture::SrgbTexture2d::new(display, raw_image).unwrap();
-    let texture = glium::texture::Texture2d::new(display, raw_image).unwrap();
-    texture
+
+    Ok(glium::texture::Texture2d::new(display, raw_image)?)
 }
@@ -0,0 +1,191 @@
+use crate::app::App;
+use crate::state::{LoadDbState, UiEvent};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use super::{frame, textbox, WidgetId, FOOTER_HEIGHT};
+use crate::layout::dynamic_matrix::Matrix;
+use crate::layout::{DynamicIds, WidgetHolder};
+use conrod_core::{
+    color,
+    widget::{self, Canvas, Text},
+    Borderable, Colorable, Labelable, Positionable, Sizeable, Widget,
+};
+
+const INPUT_HEIGHT: conrod::Scalar = 100.0;
+const PATH_INPUT_WIDTH: conrod::Scalar = 400.0;
+const BUTTON_WIDTH: conrod::Scalar = 100.0;
+const BUTTON_HEIGHT: conrod::Scalar = 30.0;
+const RECENT_TITLE_HEIGHT: conrod::Scalar = 40.0;
+const RECENT_ROW_HEIGHT: conrod::Scalar = 40.0;
+
+#[derive(Clone, Copy)]
+struct RecentEntry {
+    button: WidgetId,
+}
+
+impl WidgetHolder for RecentEntry {
+    fn allocate_ids(gen: &mut conrod_core::widget::id::Generator) -> Self {
+        RecentEntry {
+            button: gen.next(),
+        }
+    }
+}
+
+pub struct DatabaseIds {
+    root: WidgetId,
+    input_canvas: WidgetId,
+    input: textbox::TextboxIds,
+    open_button: WidgetId,
+    create_button: WidgetId,
+    recent_canvas: WidgetId,
+    recent_title: WidgetId,
+    recent_title_label: WidgetId,
+    recent_list: WidgetId,
+    recent_matrix: WidgetId,
+    recent_dynamic: DynamicIds<RecentEntry>,
+    footer_canvas: WidgetId,
+}
+
+impl DatabaseIds {
+    pub fn new(gen: &mut conrod_core::widget::id::Generator) -> Self {
+        Self {
+            root: gen.next(),
+            input_canvas: gen.next(),
+            input: textbox::TextboxIds::new(gen),
+            open_button: gen.next(),
+            create_button: gen.next(),
+            recent_canvas: gen.next(),
+            recent_title: gen.next(),
+            recent_title_label: gen.next(),
+            recent_list: gen.next(),
+            recent_matrix: gen.next(),
+            recent_dynamic: DynamicIds::default(),
+            footer_canvas: gen.next(),
+        }
+    }
+}
+
+pub fn create_ui(app: &mut App, state: &LoadDbState, updates: &mut VecDeque<UiEvent>) {
+    let ui = &mut app.ui.set_widgets();
+    let ids = &mut app.ids;
+    let blank_canvas = Canvas::new().color(color::TRANSPARENT).border(0.0);
+    frame(ui, ids, &mut app.notifications, ids.root.body, blank_canvas);
+
+    // root canvas
+    blank_canvas
+        .flow_down(&[
+            (
+                ids.database.input_canvas,
+                blank_canvas
+                    .color(color::LIGHT_CHARCOAL)
+                    .length(INPUT_HEIGHT),
+            ),
+            (
+                ids.database.recent_canvas,
+                blank_canvas.pad(20.0).flow_down(&[
+                    (
+                        ids.database.recent_title,
+                        blank_canvas.length(RECENT_TITLE_HEIGHT),
+                    ),
+                    (ids.database.recent_list, blank_canvas),
+                ]),
+            ),
+            (
+                ids.database.footer_canvas,
+                blank_canvas
+                    .color(color::DARK_CHARCOAL)
+                    .length(FOOTER_HEIGHT),
+            ),
+        ])
+        .middle_of(ids.root.body)
+        .wh_of(ids.root.body)
+        .set(ids.database.root, ui);
+
+    // Path input
+    blank_canvas
+        .w(PATH_INPUT_WIDTH)
+        .h(INPUT_HEIGHT * 0.6)
+        .mid_left_with_margin_on(ids.database.input_canvas, 20.0)
+        .set(ids.database.input.canvas, ui);
+
+    Text::new("Match database")
+        // style
+        .font_size(ui.theme.font_size_medium)
+        .left_justify()
+        .no_line_wrap()
+        .align_left_of(ids.database.input.canvas)
+        .align_top_of(ids.database.input.canvas)
+        .set(ids.database.input.label, ui);
+
+    let path_enter = textbox::textbox_field(
+        &state.path,
+        |s| updates.push_front(UiEvent::RecordDatabasePath(s)),
+        ids.database.input,
+        PATH_INPUT_WIDTH,
+        ui,
+    );
+
+    let has_path = !state.path.trim().is_empty();
+    let mut open = path_enter && has_path;
+    for _click in widget::Button::new()
+        .label("open")
+        .w_h(BUTTON_WIDTH, BUTTON_HEIGHT)
+        .color(color::ORANGE)
+        .enabled(has_path)
+        .right_from(ids.database.input.textbox, 20.0)
+        .set(ids.database.open_button, ui)
+    {
+        open = true;
+    }
+    if open {
+        updates.push_front(UiEvent::OpenDatabase(PathBuf::from(state.path.trim())))
+    }
+
+    for _click in widget::Button::new()
+        .label("create new")
+        .w_h(BUTTON_WIDTH, BUTTON_HEIGHT)
+        .color(color::LIGHT_CHARCOAL)
+        .enabled(has_path)
+        .right_from(ids.database.open_button, 10.0)
+        .set(ids.database.create_button, ui)
+    {
+        updates.push_front(UiEvent::CreateDatabase(PathBuf::from(state.path.trim())))
+    }
+
+    // Recent databases
+    let recent = state.recent();
+    let title = if recent.is_empty() {
+        "no recent databases"
+    } else {
+        "recent databases"
+    };
+    Text::new(title)
+        // style
+        .font_size(ui.theme.font_size_medium)
+        .left_justify()
+        .no_line_wrap()
+        .mid_left_of(ids.database.recent_title)
+        .set(ids.database.recent_title_label, ui);
+
+    if !recent.is_empty() {
+        let list_h = RECENT_ROW_HEIGHT * recent.len() as conrod::Scalar;
+        let elements = Matrix::new(1, recent.len(), &mut ids.database.recent_dynamic)
+            .mid_top_of(ids.database.recent_list)
+            .w_of(ids.database.recent_list)
+            .h(list_h)
+            .set(ids.database.recent_matrix, ui);
+
+        for (idx, path) in recent.iter().enumerate() {
+            let elem = elements.xy_get(0, idx);
+            let label = path.display().to_string();
+            let button = widget::Button::new()
+                .label(&label)
+                .color(color::LIGHT_CHARCOAL)
+                .w_h(elem.w, elem.h - 5.0);
+            for _click in elem.set(button, elem.inner.button, ui) {
+                updates.push_front(UiEvent::OpenDatabase(path.clone()))
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::layout::{DynamicIds, WidgetHolder, WidgetId};
 
-use conrod_core::{Point, UiCell};
+use conrod_core::UiCell;
 
 use conrod_core::widget;
 use conrod_core::widget::Widget;
@@ -34,19 +34,12 @@ impl<'a, T> Matrix<'a, T> {
     pub fn new(cols: usize, rows: usize, data: &'a mut DynamicIds<T>) -> Self {
         Matrix {
             common: widget::CommonBuilder::default(),
-            data: data,
+            data,
             style: Style::default(),
-            cols: cols,
-            rows: rows,
+            cols,
+            rows,
         }
     }
-
-    /// A builder method for adding padding to the cell.
-    pub fn cell_padding(mut self, w: Scalar, h: Scalar) -> Self {
-        self.style.cell_pad_w = Some(w);
-        self.style.cell_pad_h = Some(h);
-        self
-    }
 }
 
 impl<'a, T: Send + WidgetHolder> Widget for Matrix<'a, T> {
@@ -59,7 +52,7 @@ impl<'a, T: Send + WidgetHolder> Widget for Matrix<'a, T> {
     }
 
     fn style(&self) -> Self::Style {
-        self.style.clone()
+        self.style
     }
 
     /// Update the state of the Matrix.
@@ -68,7 +61,6 @@ impl<'a, T: Send + WidgetHolder> Widget for Matrix<'a, T> {
             id,
             state,
             rect,
-            style,
             ui,
             ..
         } = args;
@@ -80,8 +72,6 @@ impl<'a, T: Send + WidgetHolder> Widget for Matrix<'a, T> {
             state.update(|s| s.len = sz)
         }
 
-        let cell_pad_w = style.cell_pad_w(&ui.theme);
-        let cell_pad_h = style.cell_pad_h(&ui.theme);
         let (w, h) = rect.w_h();
         let elem_w = w / cols as Scalar;
         let elem_h = h / rows as Scalar;
@@ -97,8 +87,6 @@ impl<'a, T: Send + WidgetHolder> Widget for Matrix<'a, T> {
             data: self.data,
             num_rows: rows,
             num_cols: cols,
-            row: 0,
-            col: 0,
             matrix_id: id,
             elem_w,
             elem_h,
@@ -120,8 +108,6 @@ pub struct Elements<'a, T> {
     data: &'a DynamicIds<T>,
     num_rows: usize,
     num_cols: usize,
-    row: usize,
-    col: usize,
     matrix_id: widget::Id,
     pub elem_w: Scalar,
     pub elem_h: Scalar,
@@ -157,8 +143,8 @@ impl<'a, T: Copy> Elements<'a, T> {
             row: idy,
             w: self.elem_w,
             h: self.elem_h,
-            rel_x: rel_x,
-            rel_y: rel_y,
+            rel_x,
+            rel_y,
             m_x: self.m_x,
             m_y: self.m_y,
         }
@@ -167,6 +153,7 @@ impl<'a, T: Copy> Elements<'a, T> {
 
 /// Data necessary for instantiating a widget for a single `Matrix` element.
 #[derive(Copy, Clone, Debug)]
+#[allow(dead_code)]
 pub struct Element<T> {
     /// The id generated for the widget.
     pub inner: T,
@@ -0,0 +1,80 @@
+use crate::app::App;
+use crate::state::UiEvent;
+use std::collections::VecDeque;
+
+use super::{frame, WidgetId};
+use conrod_core::{
+    color,
+    widget::{self, Canvas, Text},
+    Borderable, Colorable, Labelable, Positionable, Sizeable, Widget,
+};
+
+const DIALOG_WIDTH: conrod::Scalar = 500.0;
+const DIALOG_HEIGHT: conrod::Scalar = 220.0;
+const BUTTON_WIDTH: conrod::Scalar = 100.0;
+const BUTTON_HEIGHT: conrod::Scalar = 30.0;
+
+pub struct FatalIds {
+    dialog: WidgetId,
+    title: WidgetId,
+    message: WidgetId,
+    quit_button: WidgetId,
+}
+
+impl FatalIds {
+    pub fn new(gen: &mut conrod_core::widget::id::Generator) -> Self {
+        Self {
+            dialog: gen.next(),
+            title: gen.next(),
+            message: gen.next(),
+            quit_button: gen.next(),
+        }
+    }
+}
+
+pub fn create_ui(app: &mut App, message: &str, updates: &mut VecDeque<UiEvent>) {
+    let ui = &mut app.ui.set_widgets();
+    let ids = &mut app.ids;
+    let body = Canvas::new().color(color::TRANSPARENT).border(0.0);
+    frame(ui, ids, &mut app.notifications, ids.root.body, body);
+
+    Canvas::new()
+        .color(color::DARK_CHARCOAL)
+        .border(0.0)
+        .pad(20.0)
+        .w_h(DIALOG_WIDTH, DIALOG_HEIGHT)
+        .middle_of(ids.root.body)
+        .set(ids.fatal.dialog, ui);
+
+    Text::new("Something went wrong")
+        // style
+        .font_size(ui.theme.font_size_large)
+        .color(color::LIGHT_RED)
+        .left_justify()
+        .no_line_wrap()
+        .top_left_of(ids.fatal.dialog)
+        .set(ids.fatal.title, ui);
+
+    Text::new(&format!(
+        "{}\n\nMatches already recorded are safe, but the one in progress was not saved.",
+        message
+    ))
+    // style
+    .font_size(ui.theme.font_size_medium)
+    .color(color::WHITE)
+    .left_justify()
+    .wrap_by_word()
+    .w(DIALOG_WIDTH - 40.0)
+    .down_from(ids.fatal.title, 20.0)
+    .set(ids.fatal.message, ui);
+
+    for _click in widget::Button::new()
+        .label("quit")
+        .w_h(BUTTON_WIDTH, BUTTON_HEIGHT)
+        .color(color::ORANGE)
+        .bottom_right_of(ids.fatal.dialog)
+        .set(ids.fatal.quit_button, ui)
+    {
+        updates.push_front(UiEvent::Quit)
+    }
+}
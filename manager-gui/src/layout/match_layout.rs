@@ -1,5 +1,5 @@
 use crate::app::{App, AppAssets};
-use crate::state::{MatchState, UiEvent};
+use crate::state::{MatchState, UiEvent, SUGGESTION_COUNT};
 use std::collections::VecDeque;
 
 use super::{frame, WidgetId, FOOTER_HEIGHT};
@@ -13,8 +13,8 @@ use conrod_core::{
     widget::{self, Canvas, Text},
     Borderable, Colorable, Labelable, Positionable, Sizeable, Widget,
 };
-use overwatch::overwatch_3v3::Player;
-use overwatch::{Hero, Role};
+use overwatch::overwatch_3v3::{Player, RoundOutcome};
+use overwatch::{Hero, HeroPool, Map, Role};
 
 const CONTROL_HEIGHT: conrod::Scalar = 130.0;
 const ROSTER_PLAYER_WIDTH: conrod::Scalar = 150.0;
@@ -25,12 +25,14 @@ const ROUND_RECORD_W_MAX: conrod::Scalar = PORTRAIT_MINI_HEIGHT * 6.0;
 const ROUND_RECORD_BORDER: conrod::Scalar = 1.0;
 const MATCH_HISTORY_TITLE_HEIGHT: conrod::Scalar = 40.0;
 const BATTLETAG_HEIGHT: conrod::Scalar = 25.0;
+const ENEMY_ROW_HEIGHT: conrod::Scalar = PORTRAIT_MINI_HEIGHT + 20.0;
+const ENEMY_LABEL_WIDTH: conrod::Scalar = 100.0;
+const PLAN_WIDTH: conrod::Scalar = 420.0;
 
 struct PlayerRosterIds {
     canvas: WidgetId,
     portrait_canvas: WidgetId,
     portrait_image: WidgetId,
-    battletag_canvas: WidgetId,
     battletag_label: WidgetId,
     select_button: WidgetId,
 }
@@ -41,13 +43,28 @@ impl PlayerRosterIds {
             canvas: gen.next(),
             portrait_canvas: gen.next(),
             portrait_image: gen.next(),
-            battletag_canvas: gen.next(),
             battletag_label: gen.next(),
             select_button: gen.next(),
         }
     }
 }
 
+struct EnemySlotIds {
+    canvas: WidgetId,
+    portrait_image: WidgetId,
+    select_button: WidgetId,
+}
+
+impl EnemySlotIds {
+    pub fn new(gen: &mut conrod_core::widget::id::Generator) -> Self {
+        Self {
+            canvas: gen.next(),
+            portrait_image: gen.next(),
+            select_button: gen.next(),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 struct HeroSelectPortrait {
     portrait_canvas: WidgetId,
@@ -65,6 +82,27 @@ impl WidgetHolder for HeroSelectPortrait {
     }
 }
 
+#[derive(Clone, Copy)]
+struct SuggestionEntry {
+    canvas: WidgetId,
+    heros_canvas: WidgetId,
+    heros: [WidgetId; 3],
+    score_label: WidgetId,
+    select_button: WidgetId,
+}
+
+impl WidgetHolder for SuggestionEntry {
+    fn allocate_ids(gen: &mut conrod_core::widget::id::Generator) -> Self {
+        Self {
+            canvas: gen.next(),
+            heros_canvas: gen.next(),
+            heros: [gen.next(), gen.next(), gen.next()],
+            score_label: gen.next(),
+            select_button: gen.next(),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 struct MatchHistoryEntry {
     canvas: WidgetId,
@@ -72,6 +110,7 @@ struct MatchHistoryEntry {
     heros: [WidgetId; 3],
     outcome_canvas: WidgetId,
     outcome_label: WidgetId,
+    select_button: WidgetId,
 }
 
 impl WidgetHolder for MatchHistoryEntry {
@@ -82,6 +121,7 @@ impl WidgetHolder for MatchHistoryEntry {
             heros: [gen.next(), gen.next(), gen.next()],
             outcome_canvas: gen.next(),
             outcome_label: gen.next(),
+            select_button: gen.next(),
         }
     }
 }
@@ -93,18 +133,29 @@ pub struct MatchIds {
     round_outcome_canvas: WidgetId,
     round_outcome_toggle: WidgetId,
     round_outcome_submit: WidgetId,
+    round_edit_cancel: WidgetId,
+    map_select: WidgetId,
+    enemy_canvas: WidgetId,
+    enemy_label: WidgetId,
+    enemy_slots: [EnemySlotIds; 3],
     suggestion_canvas: WidgetId,
     suggestion_matrix: WidgetId,
-    suggestion_dynamic: DynamicIds<HeroSelectPortrait>,
+    plan_label: WidgetId,
+    suggestion_dynamic: DynamicIds<SuggestionEntry>,
     match_canvas: WidgetId,
     match_history_canvas: WidgetId,
     match_history_title_canvas: WidgetId,
     match_history_title_label: WidgetId,
+    match_history_edit: WidgetId,
+    match_history_move_up: WidgetId,
+    match_history_delete: WidgetId,
     match_history_data_canvas: WidgetId,
     match_history_matrix: WidgetId,
     match_history_dynamic: DynamicIds<MatchHistoryEntry>,
     hero_selection: HeroSelectionRoleIds,
     footer: WidgetId,
+    footer_message: WidgetId,
+    footer_retry: WidgetId,
 }
 
 pub struct HeroSelectionRoleIds {
@@ -137,28 +188,43 @@ impl MatchIds {
             round_outcome_canvas: gen.next(),
             round_outcome_toggle: gen.next(),
             round_outcome_submit: gen.next(),
+            round_edit_cancel: gen.next(),
+            map_select: gen.next(),
+            enemy_canvas: gen.next(),
+            enemy_label: gen.next(),
+            enemy_slots: [
+                EnemySlotIds::new(gen),
+                EnemySlotIds::new(gen),
+                EnemySlotIds::new(gen),
+            ],
             suggestion_canvas: gen.next(),
             suggestion_matrix: gen.next(),
+            plan_label: gen.next(),
             match_canvas: gen.next(),
             match_history_canvas: gen.next(),
             match_history_title_canvas: gen.next(),
             match_history_title_label: gen.next(),
+            match_history_edit: gen.next(),
+            match_history_move_up: gen.next(),
+            match_history_delete: gen.next(),
             match_history_data_canvas: gen.next(),
             match_history_matrix: gen.next(),
             match_history_dynamic: Default::default(),
             hero_selection: HeroSelectionRoleIds::new(gen),
             footer: gen.next(),
+            footer_message: gen.next(),
+            footer_retry: gen.next(),
             suggestion_dynamic: Default::default(),
         }
     }
 }
 
 pub fn create_ui(app: &mut App, state: &MatchState, updates: &mut VecDeque<UiEvent>) {
-    let ref mut ui = app.ui.set_widgets();
-    let ref mut ids = app.ids;
-    let ref assets = app.assets;
+    let ui = &mut app.ui.set_widgets();
+    let ids = &mut app.ids;
+    let assets = &app.assets;
     let body = Canvas::new().color(color::TRANSPARENT).border(0.0);
-    frame(ui, ids, ids.root.body, body);
+    frame(ui, ids, &mut app.notifications, ids.root.body, body);
 
     let blank_canvas = Canvas::new().color(color::TRANSPARENT).border(0.0);
 
@@ -167,73 +233,72 @@ pub fn create_ui(app: &mut App, state: &MatchState, updates: &mut VecDeque<UiEve
 
     // root canvas
     blank_canvas
-        .clone()
         .color(color::GRAY)
         .flow_down(&[
             (
                 ids.match_record.control_canvas,
                 blank_canvas
-                    .clone()
                     .color(color::LIGHT_CHARCOAL)
                     .length(CONTROL_HEIGHT)
                     .flow_right(&[
                         (
                             ids.match_record.roster_players[0].canvas,
                             blank_canvas
-                                .clone()
                                 .color(color::LIGHT_ORANGE)
                                 .length(ROSTER_PLAYER_WIDTH),
                         ),
                         (
                             ids.match_record.roster_players[1].canvas,
                             blank_canvas
-                                .clone()
                                 .color(color::ORANGE)
                                 .length(ROSTER_PLAYER_WIDTH),
                         ),
                         (
                             ids.match_record.roster_players[2].canvas,
                             blank_canvas
-                                .clone()
                                 .color(color::DARK_ORANGE)
                                 .length(ROSTER_PLAYER_WIDTH),
                         ),
-                        (ids.match_record.round_outcome_canvas, blank_canvas.clone()),
+                        (ids.match_record.round_outcome_canvas, blank_canvas),
                     ]),
             ),
+            (
+                ids.match_record.enemy_canvas,
+                blank_canvas
+                    .color(color::CHARCOAL)
+                    .length(ENEMY_ROW_HEIGHT),
+            ),
             (
                 ids.match_record.suggestion_canvas,
                 blank_canvas
-                    .clone()
                     .color(color::BLACK)
-                    .length(PORTRAIT_MINI_HEIGHT * 3.0),
+                    .length(PORTRAIT_MINI_HEIGHT * SUGGESTION_COUNT as Scalar),
             ),
             (
                 ids.match_record.match_canvas,
-                blank_canvas.clone().flow_right(&[
+                blank_canvas.flow_right(&[
                     (
                         ids.match_record.match_history_canvas,
-                        blank_canvas.clone().length(round_record_width).flow_down(&[
+                        blank_canvas.length(round_record_width).flow_down(&[
                             (
                                 ids.match_record.match_history_title_canvas,
-                                blank_canvas.clone().length(MATCH_HISTORY_TITLE_HEIGHT),
+                                blank_canvas.length(MATCH_HISTORY_TITLE_HEIGHT),
                             ),
                             (
                                 ids.match_record.match_history_data_canvas,
-                                blank_canvas.clone(),
+                                blank_canvas,
                             ),
                         ]),
                     ),
                     (
                         ids.match_record.hero_selection.canvas,
-                        blank_canvas.clone().color(color::BLACK),
+                        blank_canvas.color(color::BLACK),
                     ),
                 ]),
             ),
             (
                 ids.match_record.footer,
                 blank_canvas
-                    .clone()
                     .color(color::DARK_CHARCOAL)
                     .border(0.0)
                     .length(FOOTER_HEIGHT),
@@ -248,7 +313,24 @@ pub fn create_ui(app: &mut App, state: &MatchState, updates: &mut VecDeque<UiEve
         create_roster_hero(state, player, pids, updates, assets, ui);
     }
 
-    let elements = Matrix::new(8, 4, &mut ids.match_record.hero_selection.dynamic)
+    // Enemy composition
+    Text::new("enemy")
+        // style
+        .font_size(ui.theme.font_size_medium)
+        .left_justify()
+        .no_line_wrap()
+        .mid_left_with_margin_on(ids.match_record.enemy_canvas, 20.0)
+        .set(ids.match_record.enemy_label, ui);
+
+    for slot in Player::iter() {
+        let sids = &ids.match_record.enemy_slots[slot.index()];
+        let parent = ids.match_record.enemy_canvas;
+        create_enemy_slot(state, slot, sids, parent, updates, assets, ui);
+    }
+
+    let [grid_w, grid_h] = ui.wh_of(ids.match_record.hero_selection.canvas).unwrap();
+    let (columns, rows) = hero_grid_shape(Hero::iter().count(), grid_w, grid_h);
+    let elements = Matrix::new(columns, rows, &mut ids.match_record.hero_selection.dynamic)
         .middle_of(ids.match_record.hero_selection.canvas)
         .wh_of(ids.match_record.hero_selection.canvas)
         .set(ids.match_record.hero_selection.matrix, ui);
@@ -262,53 +344,95 @@ pub fn create_ui(app: &mut App, state: &MatchState, updates: &mut VecDeque<UiEve
         .elem_h
         .min(elements.elem_w)
         .min(PORTRAIT_FULL_HEIGHT);
-    for idy in 0..4 {
-        for idx in 0..8 {
+    let selectable = state.selectable_heros();
+    for idy in 0..rows {
+        for idx in 0..columns {
             let elem = elements.xy_get(idx, idy);
             if let Some(hero) = heros.next() {
                 elem.set(
-                    blank_canvas.clone().w_h(dimm, dimm),
+                    blank_canvas.w_h(dimm, dimm),
                     elem.inner.portrait_canvas,
                     ui,
                 );
-                create_hero_selection_button(hero, state, assets, &elem.inner, updates, ui)
+                create_hero_selection_button(hero, &selectable, assets, &elem.inner, updates, ui)
             }
         }
     }
 
-    let sw = ui.w_of(ids.match_record.suggestion_canvas).unwrap();
-    let suggestions = (sw / PORTRAIT_MINI_HEIGHT as f64) as usize;
-
-    let elements = Matrix::new(suggestions, 3, &mut ids.match_record.suggestion_dynamic)
+    // Suggested comps for the next round, best first, one to a row
+    let suggestions = state.suggestions();
+    let elements = Matrix::new(1, SUGGESTION_COUNT, &mut ids.match_record.suggestion_dynamic)
         .mid_left_of(ids.match_record.suggestion_canvas)
-        .w(PORTRAIT_MINI_HEIGHT * suggestions as Scalar)
+        .w(ui.w_of(ids.match_record.suggestion_canvas).unwrap() - PLAN_WIDTH)
         .h_of(ids.match_record.suggestion_canvas)
         .set(ids.match_record.suggestion_matrix, ui);
-    let mut heros = std::iter::repeat(Hero::Ana);
-    let dimm = PORTRAIT_MINI_HEIGHT;
-    for idy in 0..3 {
-        for idx in 0..suggestions {
-            let elem = elements.xy_get(idx, idy);
-            if let Some(hero) = heros.next() {
-                elem.set(
-                    blank_canvas.clone().w_h(dimm, dimm),
-                    elem.inner.portrait_canvas,
-                    ui,
-                );
-                create_hero_selection_button(hero, state, assets, &elem.inner, updates, ui)
-            }
+
+    let portrait_size = PORTRAIT_MINI_HEIGHT;
+    for (idy, suggestion) in suggestions.iter().enumerate() {
+        let elem = elements.xy_get(0, idy);
+        elem.set(
+            blank_canvas.color(color::BLACK).w_h(elem.w, elem.h),
+            elem.inner.canvas,
+            ui,
+        );
+
+        blank_canvas
+            .color(color::BLACK)
+            .h(portrait_size)
+            .w(portrait_size * 3.0)
+            .mid_left_with_margin_on(elem.inner.canvas, 10.0)
+            .set(elem.inner.heros_canvas, ui);
+
+        for player in Player::iter() {
+            let img = assets.portrait(suggestion.comp[player.index()]);
+            widget::Image::new(img)
+                .h(portrait_size)
+                .w(portrait_size)
+                .x_relative_to(
+                    elem.inner.heros_canvas,
+                    portrait_size * ((player.index() as Scalar) - 1.0),
+                )
+                .set(elem.inner.heros[player.index()], ui);
+        }
+
+        Text::new(&format!("expected {:.0}%", suggestion.score * 100.0))
+            // style
+            .font_size(ui.theme.font_size_medium)
+            .center_justify()
+            .no_line_wrap()
+            .right_from(elem.inner.heros_canvas, 20.0)
+            .set(elem.inner.score_label, ui);
+
+        let select = widget::Button::new()
+            .color(color::TRANSPARENT)
+            .wh_of(elem.inner.canvas)
+            .middle_of(elem.inner.canvas);
+
+        for _event in select.set(elem.inner.select_button, ui) {
+            updates.push_front(UiEvent::RoundSelectComp(suggestion.comp))
         }
     }
 
-    let outcome_color = |win: Option<bool>| match win {
-        Some(true) => color::DARK_GREEN,
-        Some(false) => color::LIGHT_RED,
+    // How the remaining heroes could be spread over the rounds we still need
+    Text::new(&state.plan().to_string())
+        // style
+        .font_size(ui.theme.font_size_small)
+        .left_justify()
+        .w(PLAN_WIDTH - 20.0)
+        .mid_right_with_margin_on(ids.match_record.suggestion_canvas, 10.0)
+        .set(ids.match_record.plan_label, ui);
+
+    let outcome_color = |outcome: Option<RoundOutcome>| match outcome {
+        Some(RoundOutcome::Win) => color::DARK_GREEN,
+        Some(RoundOutcome::Loss) => color::LIGHT_RED,
+        Some(RoundOutcome::Draw) => color::LIGHT_YELLOW,
         None => color::LIGHT_GRAY,
     };
 
-    let outcome_label = |win: Option<bool>| match win {
-        Some(true) => "victory",
-        Some(false) => "defeat",
+    let outcome_label = |outcome: Option<RoundOutcome>| match outcome {
+        Some(RoundOutcome::Win) => "victory",
+        Some(RoundOutcome::Loss) => "defeat",
+        Some(RoundOutcome::Draw) => "draw",
         None => "unknown",
     };
 
@@ -316,16 +440,37 @@ pub fn create_ui(app: &mut App, state: &MatchState, updates: &mut VecDeque<UiEve
     for _ in widget::Toggle::new(false)
         .w_h(100.0, 40.0)
         .top_right_with_margins_on(ids.match_record.round_outcome_canvas, 20.0, 20.0)
-        .label(outcome_label(state.get_win()))
-        .color(outcome_color(state.get_win()))
+        .label(outcome_label(state.get_outcome()))
+        .color(outcome_color(state.get_outcome()))
         .set(ids.match_record.round_outcome_toggle, ui)
     {
         updates.push_front(UiEvent::RoundToggleOutcome)
     }
 
-    if state.validate() {
+    // Map picker, the first entry clears the map
+    let maps = std::iter::once("unknown map")
+        .chain(Map::iter().map(Map::name))
+        .collect::<Vec<_>>();
+    let selected_map = state
+        .map()
+        .and_then(|m| Map::iter().position(|other| other == m))
+        .map(|idx| idx + 1)
+        .unwrap_or(0);
+    if let Some(idx) = widget::DropDownList::new(&maps, Some(selected_map))
+        .w_h(200.0, 40.0)
+        .max_visible_items(8)
+        .scrollbar_on_top()
+        .color(color::LIGHT_CHARCOAL)
+        .top_left_with_margins_on(ids.match_record.round_outcome_canvas, 20.0, 20.0)
+        .set(ids.match_record.map_select, ui)
+    {
+        let map = if idx == 0 { None } else { Map::iter().nth(idx - 1) };
+        updates.push_front(UiEvent::MatchSelectMap(map))
+    }
+
+    if state.validate() && state.accepts_round() {
         for _event in widget::Button::new()
-            .label("Submit")
+            .label(if state.editing().is_some() { "Save" } else { "Submit" })
             .w_h(100.0, 40.0)
             .color(color::LIGHT_CHARCOAL)
             .bottom_right_with_margins_on(ids.match_record.round_outcome_canvas, 20.0, 20.0)
@@ -335,16 +480,71 @@ pub fn create_ui(app: &mut App, state: &MatchState, updates: &mut VecDeque<UiEve
         }
     }
 
+    if state.editing().is_some() {
+        for _event in widget::Button::new()
+            .label("Cancel")
+            .w_h(100.0, 40.0)
+            .color(color::LIGHT_CHARCOAL)
+            .bottom_right_with_margins_on(ids.match_record.round_outcome_canvas, 20.0, 140.0)
+            .set(ids.match_record.round_edit_cancel, ui)
+        {
+            updates.push_front(UiEvent::MatchCancelEdit)
+        }
+    }
+
+    if state.save_failed() {
+        Text::new("match not saved")
+            // style
+            .font_size(ui.theme.font_size_medium)
+            .color(color::LIGHT_RED)
+            .left_justify()
+            .no_line_wrap()
+            .mid_left_with_margin_on(ids.match_record.footer, 20.0)
+            .set(ids.match_record.footer_message, ui);
+
+        for _event in widget::Button::new()
+            .label("Retry")
+            .w_h(100.0, FOOTER_HEIGHT - 20.0)
+            .color(color::LIGHT_CHARCOAL)
+            .mid_right_with_margin_on(ids.match_record.footer, 20.0)
+            .set(ids.match_record.footer_retry, ui)
+        {
+            updates.push_front(UiEvent::RetrySaveMatch)
+        }
+    }
+
     // Match History
 
-    Text::new("match history")
-        // style
-        .font_size(ui.theme.font_size_medium)
-        .center_justify()
-        .w_of(ids.match_record.match_history_title_canvas)
-        .middle_of(ids.match_record.match_history_title_canvas)
-        .no_line_wrap()
-        .set(ids.match_record.match_history_title_label, ui);
+    if state.selected_round().is_some() && state.editing().is_none() {
+        // actions on the selected round take the title's place
+        let title = ids.match_record.match_history_title_canvas;
+        let button_w = (round_record_width - 40.0) / 3.0;
+        let actions = [
+            ("edit", ids.match_record.match_history_edit, UiEvent::MatchEditRound),
+            ("up", ids.match_record.match_history_move_up, UiEvent::MatchMoveRoundUp),
+            ("delete", ids.match_record.match_history_delete, UiEvent::MatchDeleteRound),
+        ];
+        for (idx, (label, id, event)) in actions.iter().enumerate() {
+            for _event in widget::Button::new()
+                .label(label)
+                .w_h(button_w, MATCH_HISTORY_TITLE_HEIGHT - 10.0)
+                .color(color::LIGHT_CHARCOAL)
+                .mid_left_with_margin_on(title, 10.0 + (button_w + 10.0) * idx as Scalar)
+                .set(*id, ui)
+            {
+                updates.push_front(event.clone())
+            }
+        }
+    } else {
+        Text::new("match history")
+            // style
+            .font_size(ui.theme.font_size_medium)
+            .center_justify()
+            .w_of(ids.match_record.match_history_title_canvas)
+            .middle_of(ids.match_record.match_history_title_canvas)
+            .no_line_wrap()
+            .set(ids.match_record.match_history_title_label, ui);
+    }
 
     // This should never be more than five, but in case we need to support ties, this is easy enough
     let rows = 5.max(state.match_len());
@@ -360,9 +560,16 @@ pub fn create_ui(app: &mut App, state: &MatchState, updates: &mut VecDeque<UiEve
     let heros_width = portrait_size * 3.0;
     for (ridx, round) in state.match_iter().enumerate() {
         let elem = elements.xy_get(0, ridx);
+        let highlight = if state.editing() == Some(ridx) {
+            color::LIGHT_RED
+        } else if state.selected_round() == Some(ridx) {
+            color::LIGHT_CHARCOAL
+        } else {
+            color::TRANSPARENT
+        };
         elem.set(
             blank_canvas
-                .clone()
+                .color(highlight)
                 .border(ROUND_RECORD_BORDER)
                 .w_h(elem.w, elem.h),
             elem.inner.canvas,
@@ -370,7 +577,6 @@ pub fn create_ui(app: &mut App, state: &MatchState, updates: &mut VecDeque<UiEve
         );
 
         blank_canvas
-            .clone()
             .color(color::BLACK)
             .h(portrait_size)
             .w(heros_width)
@@ -379,7 +585,7 @@ pub fn create_ui(app: &mut App, state: &MatchState, updates: &mut VecDeque<UiEve
 
         for player in Player::iter() {
             let hero = round.get_hero(player);
-            let img = assets.portraits[&hero];
+            let img = assets.portrait(hero);
 
             widget::Image::new(img)
                 .h(portrait_size)
@@ -394,14 +600,13 @@ pub fn create_ui(app: &mut App, state: &MatchState, updates: &mut VecDeque<UiEve
         let victory_bar = (round_record_width - heros_width) * 0.7;
 
         blank_canvas
-            .clone()
-            .color(outcome_color(Some(round.win)))
+            .color(outcome_color(Some(round.outcome)))
             .h(portrait_size * 0.7)
             .w(victory_bar)
             .mid_left_with_margin_on(elem.inner.canvas, heros_width + 20.0)
             .set(elem.inner.outcome_canvas, ui);
 
-        Text::new(outcome_label(Some(round.win)))
+        Text::new(outcome_label(Some(round.outcome)))
             // style
             .font_size(ui.theme.font_size_medium)
             .center_justify()
@@ -409,27 +614,61 @@ pub fn create_ui(app: &mut App, state: &MatchState, updates: &mut VecDeque<UiEve
             .middle_of(elem.inner.outcome_canvas)
             .no_line_wrap()
             .set(elem.inner.outcome_label, ui);
+
+        let select = widget::Button::new()
+            .color(color::TRANSPARENT)
+            .wh_of(elem.inner.canvas)
+            .middle_of(elem.inner.canvas);
+
+        for _event in select.set(elem.inner.select_button, ui) {
+            updates.push_front(UiEvent::MatchSelectRound(ridx))
+        }
+    }
+}
+
+/// Columns and rows for a grid of `heros` square portraits that makes them as big as they
+/// can be in a `w` by `h` area.
+fn hero_grid_shape(heros: usize, w: Scalar, h: Scalar) -> (usize, usize) {
+    let heros = heros.max(1);
+    let mut best = (heros, 1);
+    let mut best_size = 0.0;
+    for rows in 1..=heros {
+        let columns = heros.div_ceil(rows);
+        let size = (w / columns as Scalar).min(h / rows as Scalar);
+        if size > best_size {
+            best = (columns, rows);
+            best_size = size;
+        }
     }
+    best
 }
 
 fn create_hero_selection_button(
     hero: Hero,
-    state: &MatchState,
+    selectable: &HeroPool,
     assets: &AppAssets,
     ids: &HeroSelectPortrait,
     updates: &mut VecDeque<UiEvent>,
     ui: &mut UiCell,
 ) {
-    let img = assets.portraits[&hero];
-    let button = widget::Button::image(img)
+    let img = assets.portrait(hero);
+    let mut button = widget::Button::image(img)
         .middle_of(ids.portrait_canvas)
         .wh_of(ids.portrait_canvas);
+    // Every bundled hero has a portrait, but one added by an overriding hero data file may
+    // not, and the placeholders all look the same, so name the hero
+    if !assets.has_portrait(hero) {
+        button = button
+            .label(hero.name())
+            .label_font_size(ui.theme.font_size_small)
+            .label_color(color::WHITE);
+    }
 
     for _click in button.set(ids.portrait_image, ui) {
         updates.push_front(UiEvent::RoundSelectHero(hero))
     }
 
-    if state.is_used(hero) {
+    if !selectable.contains(&hero) {
         widget::Canvas::new()
             .color(color::Color::Rgba(0.3, 0.3, 0.3, 0.8))
             .middle_of(ids.portrait_canvas)
@@ -446,7 +685,7 @@ fn create_roster_hero(
     assets: &AppAssets,
     ui: &mut conrod_core::UiCell,
 ) {
-    if let Some(img) = state.get_hero(player).map(|h| assets.portraits[&h]) {
+    if let Some(img) = state.get_hero(player).map(|h| assets.portrait(h)) {
         let roster_h = ui.h_of(ids.canvas).unwrap();
         let portrait_buffer = (roster_h - PORTRAIT_FULL_HEIGHT - BATTLETAG_HEIGHT) / 2.0;
         widget::Canvas::new()
@@ -478,3 +717,42 @@ fn create_roster_hero(
         updates.push_front(UiEvent::RoundSelectPlayer(player))
     }
 }
+
+fn create_enemy_slot(
+    state: &MatchState,
+    slot: Player,
+    ids: &EnemySlotIds,
+    parent: WidgetId,
+    updates: &mut VecDeque<UiEvent>,
+    assets: &AppAssets,
+    ui: &mut conrod_core::UiCell,
+) {
+    let selected = state.selected_enemy() == Some(slot);
+    let margin = ENEMY_LABEL_WIDTH + (PORTRAIT_MINI_HEIGHT + 10.0) * slot.index() as Scalar;
+    widget::Canvas::new()
+        .color(if selected {
+            color::LIGHT_RED
+        } else {
+            color::DARK_CHARCOAL
+        })
+        .border(0.0)
+        .w_h(PORTRAIT_MINI_HEIGHT, PORTRAIT_MINI_HEIGHT)
+        .mid_left_with_margin_on(parent, margin)
+        .set(ids.canvas, ui);
+
+    if let Some(img) = state.get_enemy_hero(slot).map(|h| assets.portrait(h)) {
+        widget::Image::new(img)
+            .wh_of(ids.canvas)
+            .middle_of(ids.canvas)
+            .set(ids.portrait_image, ui);
+    }
+
+    let select = widget::Button::new()
+        .color(color::TRANSPARENT)
+        .wh_of(ids.canvas)
+        .middle_of(ids.canvas);
+
+    for _event in select.set(ids.select_button, ui) {
+        updates.push_front(UiEvent::RoundSelectEnemy(slot))
+    }
+}
@@ -1,5 +1,8 @@
 use crate::app::App;
+use crate::layout::database_layout::DatabaseIds;
+use crate::layout::fatal_layout::FatalIds;
 use crate::layout::roster_layout::RosterIds;
+use crate::notify::{Notifications, Severity};
 use crate::state::State;
 use std::collections::VecDeque;
 
@@ -10,7 +13,6 @@ const FOOTER_HEIGHT: conrod::Scalar = 50.0;
 pub const BACKGROUND_RGB: [f32; 3] = [0.0625, 0.46875, 0.3125];
 pub const BACKGROUND: conrod_core::Color =
     conrod_core::Color::Rgba(BACKGROUND_RGB[0], BACKGROUND_RGB[1], BACKGROUND_RGB[2], 1.0);
-pub const BATTLETAG_COLOR: conrod_core::Color = conrod_core::color::LIGHT_BLUE;
 
 pub use dynamic_ids::{DynamicIds, WidgetHolder};
 
@@ -39,12 +41,6 @@ mod dynamic_ids {
         }
     }
 
-    impl<T> DynamicIds<T> {
-        pub fn len(&self) -> usize {
-            self.inner.len()
-        }
-    }
-
     impl<T: WidgetHolder> DynamicIds<T> {
         pub fn resize(&mut self, size: usize, gen: &mut Generator) {
             while self.inner.len() <= size {
@@ -62,16 +58,20 @@ mod dynamic_ids {
 
 pub struct Ids {
     root: RootIds,
+    database: database_layout::DatabaseIds,
     roster: roster_layout::RosterIds,
     match_record: match_layout::MatchIds,
+    fatal: fatal_layout::FatalIds,
 }
 
 impl Ids {
     pub fn new(gen: &mut conrod_core::widget::id::Generator) -> Ids {
         Ids {
             root: RootIds::new(gen),
+            database: DatabaseIds::new(gen),
             roster: RosterIds::new(gen),
             match_record: MatchIds::new(gen),
+            fatal: FatalIds::new(gen),
         }
     }
 }
@@ -79,6 +79,8 @@ impl Ids {
 pub struct RootIds {
     root: WidgetId,
     header: WidgetId,
+    banner_label: WidgetId,
+    banner_dismiss: WidgetId,
     body: WidgetId,
 }
 
@@ -87,6 +89,8 @@ impl RootIds {
         RootIds {
             root: gen.next(),
             header: gen.next(),
+            banner_label: gen.next(),
+            banner_dismiss: gen.next(),
             body: gen.next(),
         }
     }
@@ -97,28 +101,47 @@ pub fn create_ui(app: &mut App, state: &mut State) -> bool {
     let mut updates = VecDeque::new();
 
     match state {
+        State::LoadDatabase(load_db_state) => {
+            self::database_layout::create_ui(app, load_db_state, &mut updates)
+        },
         State::RosterSelect(roster_state, _) => {
             self::roster_layout::create_ui(app, roster_state, &mut updates)
         },
         State::Match(match_state, _) => self::match_layout::create_ui(app, match_state, &mut updates),
+        State::Fatal(message) => self::fatal_layout::create_ui(app, message, &mut updates),
         State::Exit => (),
-        _ => unimplemented!("unknown window state, can not draw UI"),
+        State::Dummy => unreachable!("the dummy state only exists during a transition"),
     }
 
     let update = !updates.is_empty();
-    state.transform(updates.drain(..));
+    state.transform(updates.drain(..), &mut app.notifications);
     update
 }
 
 use crate::layout::match_layout::MatchIds;
 
 use conrod_core::{
-    color, widget::Canvas, Borderable, Colorable, Labelable, Positionable, Sizeable, UiCell, Widget,
+    color,
+    widget::{self, Canvas, Text},
+    Borderable, Colorable, Labelable, Positionable, Sizeable, Widget,
 };
 
-fn frame(ui: &mut conrod_core::UiCell, ids: &Ids, body_id: WidgetId, body: Canvas) {
+/// Lay out the header and the screen's `body`, with the latest notification as a banner
+/// across the header.
+fn frame(
+    ui: &mut conrod_core::UiCell,
+    ids: &Ids,
+    notifications: &mut Notifications,
+    body_id: WidgetId,
+    body: Canvas,
+) {
+    let banner_color = match notifications.latest().map(|n| n.severity) {
+        Some(Severity::Error) => color::DARK_RED,
+        Some(Severity::Warning) => color::DARK_YELLOW,
+        None => color::DARK_CHARCOAL,
+    };
     let header = Canvas::new()
-        .color(color::DARK_CHARCOAL)
+        .color(banner_color)
         .border(0.0)
         .length(HEADER_HEIGHT);
 
@@ -127,10 +150,43 @@ fn frame(ui: &mut conrod_core::UiCell, ids: &Ids, body_id: WidgetId, body: Canva
         .border(0.0)
         .flow_down(&[(ids.root.header, header), (body_id, body)])
         .set(ids.root.root, ui);
+
+    let notification = match notifications.latest() {
+        Some(notification) => notification,
+        None => return,
+    };
+    let message = match notifications.len() {
+        1 => notification.message.clone(),
+        n => format!("{} (+{} more)", notification.message, n - 1),
+    };
+    Text::new(&message)
+        // style
+        .font_size(ui.theme.font_size_medium)
+        .color(color::WHITE)
+        .left_justify()
+        .no_line_wrap()
+        .mid_left_with_margin_on(ids.root.header, 10.0)
+        .set(ids.root.banner_label, ui);
+
+    for _click in widget::Button::new()
+        .label("x")
+        .color(color::LIGHT_CHARCOAL)
+        .w_h(HEADER_HEIGHT - 6.0, HEADER_HEIGHT - 6.0)
+        .mid_right_with_margin_on(ids.root.header, 3.0)
+        .set(ids.root.banner_dismiss, ui)
+    {
+        notifications.dismiss_latest()
+    }
 }
 
+mod database_layout;
+
+mod fatal_layout;
+
 mod dynamic_matrix;
 
+mod textbox;
+
 mod roster_layout;
 
 mod match_layout;
@@ -2,131 +2,94 @@ use crate::app::App;
 use crate::state::{RosterSelectState, UiEvent};
 use std::collections::VecDeque;
 
-use super::{frame, WidgetId, FOOTER_HEIGHT, HEADER_HEIGHT};
+use super::{frame, textbox, WidgetId, FOOTER_HEIGHT};
+use crate::layout::dynamic_matrix::Matrix;
+use crate::layout::{DynamicIds, WidgetHolder};
 use conrod_core::{
     color,
     widget::{self, Canvas, Text},
     Borderable, Colorable, Labelable, Positionable, Sizeable, Widget,
 };
-use overwatch::overwatch_3v3::Player;
+use overwatch::overwatch_3v3::{Player, Roster};
 
-const LOGIN_WIDTH: conrod::Scalar = 300.0;
-const LOGIN_HEIGHT: conrod::Scalar = 200.0;
 const BATTLETAG_PADDING: conrod::Scalar = 5.0;
-const LOGIN_LOWER_SECTION_HEIGHT: conrod::Scalar = (LOGIN_HEIGHT - HEADER_HEIGHT) / 3.0;
 
 const INTERACTIVE_HEIGHT: conrod::Scalar = 100.00;
 const BATTLETAG_INTERACTIVE_WIDTH: conrod::Scalar = 200.00;
 const BATTLETAG_DISPLAY_WIDTH: conrod::Scalar = 150.0;
 const PLAY_BUTTON_WIDTH: conrod::Scalar = 50.0;
 const PLAY_BUTTON_HIGHT: conrod::Scalar = 30.0;
+const SUGGESTION_HEIGHT: conrod::Scalar = 30.0;
+const TEAM_TITLE_HEIGHT: conrod::Scalar = 40.0;
+const TEAM_ROW_HEIGHT: conrod::Scalar = 40.0;
+const TEAM_NAME_WIDTH: conrod::Scalar = 200.0;
+const TEAM_BUTTON_WIDTH: conrod::Scalar = 100.0;
+const DELETE_BUTTON_WIDTH: conrod::Scalar = 30.0;
+
+#[derive(Clone, Copy)]
+struct SuggestionEntry {
+    button: WidgetId,
+}
 
-mod textbox {
-    use crate::layout::WidgetId;
-
-    const TEXTBOX_HEIGHT: Scalar = 30.0;
-    const TEXTBOX_PADDING: Scalar = 10.0;
-
-    use conrod_core::{
-        widget::{self, Canvas, Text, TextBox},
-        Borderable, Colorable, Labelable, Positionable, Scalar, Sizeable, Widget,
-    };
-
-    #[derive(Debug, Clone, Copy)]
-    pub struct TextboxIds {
-        pub canvas: WidgetId,
-        pub textbox: WidgetId,
-        pub label: WidgetId,
-    }
-
-    impl TextboxIds {
-        pub fn new(gen: &mut conrod_core::widget::id::Generator) -> Self {
-            Self {
-                canvas: gen.next(),
-                textbox: gen.next(),
-                label: gen.next(),
-            }
+impl WidgetHolder for SuggestionEntry {
+    fn allocate_ids(gen: &mut conrod_core::widget::id::Generator) -> Self {
+        SuggestionEntry {
+            button: gen.next(),
         }
     }
+}
 
-    pub fn textbox_field<F: FnMut(String)>(
-        text: &str,
-        mut update: F,
-        ids: TextboxIds,
-        width: conrod::Scalar,
-        ui: &mut conrod_core::UiCell,
-    ) -> bool {
-        use conrod_core::widget::text_box::Event as TextBoxEvent;
+#[derive(Clone, Copy)]
+struct TeamEntry {
+    canvas: WidgetId,
+    load_button: WidgetId,
+    delete_button: WidgetId,
+}
 
-        let events = TextBox::new(&text)
-            // style
-            .w_h(width, TEXTBOX_HEIGHT)
-            .font_size(ui.theme.font_size_medium)
-            .left_justify()
-            .pad_text(5.0)
-            // position
-            .mid_bottom_of(ids.canvas)
-            .set(ids.textbox, ui);
-
-        let mut enter_pressed = false;
-
-        for event in events.into_iter() {
-            match event {
-                TextBoxEvent::Update(s) => {
-                    update(s);
-                },
-                TextBoxEvent::Enter => {
-                    enter_pressed = true;
-                    break;
-                },
-            }
+impl WidgetHolder for TeamEntry {
+    fn allocate_ids(gen: &mut conrod_core::widget::id::Generator) -> Self {
+        TeamEntry {
+            canvas: gen.next(),
+            load_button: gen.next(),
+            delete_button: gen.next(),
         }
-        enter_pressed
-    }
-
-    pub fn textbox_label(text: &str, ids: TextboxIds, ui: &mut conrod_core::UiCell) {
-        Text::new(text)
-            // style
-            .font_size(ui.theme.font_size_small)
-            .center_justify()
-            .no_line_wrap()
-            // position
-            //.mid_left_with_margin_on(ids.canvas, TEXTBOX_PADDING)
-            .top_left_with_margin_on(ids.canvas, TEXTBOX_PADDING)
-            .set(ids.label, ui);
     }
 }
 
 pub struct RosterIds {
     root: WidgetId,
-    header_canvas: WidgetId,
-    header_label: WidgetId,
 
     interactive_canvas: WidgetId,
     input_canvas: WidgetId,
     input: textbox::TextboxIds,
-    input_add_button: WidgetId,
+    suggestion_matrix: WidgetId,
+    suggestion_dynamic: DynamicIds<SuggestionEntry>,
 
     roster_canvas: WidgetId,
     roster_display_area: WidgetId,
     roster_players: [PlayerRosterIds; 3],
     battletags_canvas: WidgetId,
+    team_title: WidgetId,
+    team_title_label: WidgetId,
+    team_list: WidgetId,
+    team_matrix: WidgetId,
+    team_dynamic: DynamicIds<TeamEntry>,
 
     footer_canvas: WidgetId,
+    team_name: textbox::TextboxIds,
+    save_team_button: WidgetId,
     play_button: WidgetId,
-    play_label: WidgetId,
 }
 
 impl RosterIds {
     pub fn new(gen: &mut conrod_core::widget::id::Generator) -> Self {
         Self {
             root: gen.next(),
-            header_canvas: gen.next(),
-            header_label: gen.next(),
             interactive_canvas: gen.next(),
             input_canvas: gen.next(),
             input: textbox::TextboxIds::new(gen),
-            input_add_button: gen.next(),
+            suggestion_matrix: gen.next(),
+            suggestion_dynamic: DynamicIds::default(),
             roster_canvas: gen.next(),
             roster_display_area: gen.next(),
             roster_players: [
@@ -135,9 +98,15 @@ impl RosterIds {
                 PlayerRosterIds::new(gen),
             ],
             battletags_canvas: gen.next(),
+            team_title: gen.next(),
+            team_title_label: gen.next(),
+            team_list: gen.next(),
+            team_matrix: gen.next(),
+            team_dynamic: DynamicIds::default(),
             footer_canvas: gen.next(),
+            team_name: textbox::TextboxIds::new(gen),
+            save_team_button: gen.next(),
             play_button: gen.next(),
-            play_label: gen.next(),
         }
     }
 }
@@ -162,10 +131,10 @@ impl PlayerRosterIds {
 }
 
 pub fn create_ui(app: &mut App, state: &RosterSelectState, updates: &mut VecDeque<UiEvent>) {
-    let ref mut ui = app.ui.set_widgets();
-    let ref ids = app.ids;
+    let ui = &mut app.ui.set_widgets();
+    let ids = &mut app.ids;
     let body = Canvas::new().color(color::TRANSPARENT).border(0.0);
-    frame(ui, ids, ids.root.body, body);
+    frame(ui, ids, &mut app.notifications, ids.root.body, body);
 
     let interactive_canvas = Canvas::new()
         .color(color::LIGHT_CHARCOAL)
@@ -199,14 +168,19 @@ pub fn create_ui(app: &mut App, state: &RosterSelectState, updates: &mut VecDequ
                     (
                         ids.roster.input_canvas,
                         interactive_split
-                            .clone()
                             .length(BATTLETAG_INTERACTIVE_WIDTH),
                     ),
-                    (ids.roster.roster_canvas, interactive_split.clone()),
+                    (ids.roster.roster_canvas, interactive_split),
+                ]),
+            ),
+            (
+                ids.roster.battletags_canvas,
+                battletags_matrix.pad(20.0).flow_down(&[
+                    (ids.roster.team_title, battletags_matrix.length(TEAM_TITLE_HEIGHT)),
+                    (ids.roster.team_list, battletags_matrix),
                 ]),
             ),
-            (ids.roster.battletags_canvas, battletags_matrix.clone()),
-            (ids.roster.footer_canvas, footer_canvas.clone()),
+            (ids.roster.footer_canvas, footer_canvas),
         ])
         .middle_of(ids.root.body)
         .wh_of(ids.root.body)
@@ -251,15 +225,15 @@ pub fn create_ui(app: &mut App, state: &RosterSelectState, updates: &mut VecDequ
         .flow_right(&[
             (
                 ids.roster.roster_players[0].canvas,
-                interactive_split.clone().color(color::LIGHT_ORANGE),
+                interactive_split.color(color::LIGHT_ORANGE),
             ),
             (
                 ids.roster.roster_players[1].canvas,
-                interactive_split.clone().color(color::ORANGE),
+                interactive_split.color(color::ORANGE),
             ),
             (
                 ids.roster.roster_players[2].canvas,
-                interactive_split.clone().color(color::DARK_ORANGE),
+                interactive_split.color(color::DARK_ORANGE),
             ),
         ])
         .set(ids.roster.roster_display_area, ui);
@@ -283,6 +257,136 @@ pub fn create_ui(app: &mut App, state: &RosterSelectState, updates: &mut VecDequ
         for _event in play.set(ids.roster.play_button, ui) {
             updates.push_front(UiEvent::RosterPlay)
         }
+
+        // Save as team
+        Canvas::new()
+            .color(color::TRANSPARENT)
+            .border(0.0)
+            .w_h(TEAM_NAME_WIDTH, FOOTER_HEIGHT)
+            .mid_left_with_margin_on(ids.roster.footer_canvas, 20.0)
+            .set(ids.roster.team_name.canvas, ui);
+
+        let name_enter = textbox::textbox_field(
+            &state.team_name,
+            |s| updates.push_front(UiEvent::RecordTeamName(s)),
+            ids.roster.team_name,
+            TEAM_NAME_WIDTH,
+            ui,
+        );
+        let has_name = !state.team_name.trim().is_empty();
+        let mut save = name_enter && has_name;
+        for _click in widget::Button::new()
+            .label("save team")
+            .w_h(TEAM_BUTTON_WIDTH, PLAY_BUTTON_HIGHT)
+            .color(color::LIGHT_CHARCOAL)
+            .enabled(has_name)
+            .right_from(ids.roster.team_name.textbox, 10.0)
+            .set(ids.roster.save_team_button, ui)
+        {
+            save = true;
+        }
+        if save {
+            updates.push_front(UiEvent::SaveTeam)
+        }
+    }
+
+    create_team_list(state, &mut ids.roster, updates, ui);
+
+    // Autocomplete, drawn last so it sits on top of the battletag list
+    let suggestions = state.suggestions();
+    if !suggestions.is_empty() {
+        let elements = Matrix::new(1, suggestions.len(), &mut ids.roster.suggestion_dynamic)
+            .w(BATTLETAG_INTERACTIVE_WIDTH * 0.8)
+            .h(SUGGESTION_HEIGHT * suggestions.len() as conrod::Scalar)
+            .down_from(ids.roster.input.textbox, 0.0)
+            .set(ids.roster.suggestion_matrix, ui);
+
+        for (idx, battletag) in suggestions.iter().enumerate() {
+            let elem = elements.xy_get(0, idx);
+            let color = if state.highlighted() == Some(idx) {
+                color::ORANGE
+            } else {
+                color::LIGHT_CHARCOAL
+            };
+            let button = widget::Button::new()
+                .label(battletag.as_str())
+                .color(color)
+                .w_h(elem.w, elem.h);
+            for _click in elem.set(button, elem.inner.button, ui) {
+                updates.push_front(UiEvent::PickSuggestion(idx))
+            }
+        }
+    }
+}
+
+/// Saved teams, each loading its players onto the roster with one click.
+fn create_team_list(
+    state: &RosterSelectState,
+    ids: &mut RosterIds,
+    updates: &mut VecDeque<UiEvent>,
+    ui: &mut conrod_core::UiCell,
+) {
+    let teams = state.teams();
+    let title = if teams.is_empty() {
+        "no saved teams"
+    } else {
+        "saved teams"
+    };
+    Text::new(title)
+        // style
+        .font_size(ui.theme.font_size_medium)
+        .left_justify()
+        .no_line_wrap()
+        .mid_left_of(ids.team_title)
+        .set(ids.team_title_label, ui);
+
+    if teams.is_empty() {
+        return;
+    }
+    let selected = state.selected_team().map(|t| t.id);
+    let elements = Matrix::new(1, teams.len(), &mut ids.team_dynamic)
+        .mid_top_of(ids.team_list)
+        .w_of(ids.team_list)
+        .h(TEAM_ROW_HEIGHT * teams.len() as conrod::Scalar)
+        .set(ids.team_matrix, ui);
+
+    for (idx, team) in teams.iter().enumerate() {
+        let elem = elements.xy_get(0, idx);
+        elem.set(
+            Canvas::new()
+                .color(color::TRANSPARENT)
+                .border(0.0)
+                .w_h(elem.w, elem.h - 5.0),
+            elem.inner.canvas,
+            ui,
+        );
+
+        let Roster(p1, p2, p3) = &team.roster;
+        let label = format!("{}: {}, {}, {}", team.name, p1, p2, p3);
+        let color = if selected == Some(team.id) {
+            color::ORANGE
+        } else {
+            color::LIGHT_CHARCOAL
+        };
+        for _click in widget::Button::new()
+            .label(&label)
+            .color(color)
+            .w_h(elem.w - DELETE_BUTTON_WIDTH - 5.0, elem.h - 5.0)
+            .mid_left_of(elem.inner.canvas)
+            .set(elem.inner.load_button, ui)
+        {
+            updates.push_front(UiEvent::LoadTeam(idx))
+        }
+
+        for _click in widget::Button::new()
+            .label("x")
+            .color(color::LIGHT_RED)
+            .w_h(DELETE_BUTTON_WIDTH, elem.h - 5.0)
+            .mid_right_of(elem.inner.canvas)
+            .set(elem.inner.delete_button, ui)
+        {
+            updates.push_front(UiEvent::DeleteTeam(idx))
+        }
     }
 }
 
@@ -0,0 +1,57 @@
+use crate::layout::WidgetId;
+
+const TEXTBOX_HEIGHT: Scalar = 30.0;
+
+use conrod_core::{widget::TextBox, Positionable, Scalar, Sizeable, Widget};
+
+#[derive(Debug, Clone, Copy)]
+pub struct TextboxIds {
+    pub canvas: WidgetId,
+    pub textbox: WidgetId,
+    pub label: WidgetId,
+}
+
+impl TextboxIds {
+    pub fn new(gen: &mut conrod_core::widget::id::Generator) -> Self {
+        Self {
+            canvas: gen.next(),
+            textbox: gen.next(),
+            label: gen.next(),
+        }
+    }
+}
+
+pub fn textbox_field<F: FnMut(String)>(
+    text: &str,
+    mut update: F,
+    ids: TextboxIds,
+    width: conrod::Scalar,
+    ui: &mut conrod_core::UiCell,
+) -> bool {
+    use conrod_core::widget::text_box::Event as TextBoxEvent;
+
+    let events = TextBox::new(text)
+        // style
+        .w_h(width, TEXTBOX_HEIGHT)
+        .font_size(ui.theme.font_size_medium)
+        .left_justify()
+        .pad_text(5.0)
+        // position
+        .mid_bottom_of(ids.canvas)
+        .set(ids.textbox, ui);
+
+    let mut enter_pressed = false;
+
+    for event in events.into_iter() {
+        match event {
+            TextBoxEvent::Update(s) => {
+                update(s);
+            },
+            TextBoxEvent::Enter => {
+                enter_pressed = true;
+                break;
+            },
+        }
+    }
+    enter_pressed
+}
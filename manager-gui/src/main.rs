@@ -1,3 +1,6 @@
+// conrod's widget derives emit their impls inside an anonymous const
+#![allow(non_local_definitions)]
+
 #[macro_use]
 extern crate log;
 
@@ -12,15 +15,140 @@ extern crate image;
 mod app;
 mod image_util;
 mod layout;
+mod notify;
+mod recent;
+mod script;
 mod state;
 mod support;
 mod window_mgmt;
 
+use crate::state::UiEvent;
+use clap::{App, Arg, ArgMatches};
+use std::path::Path;
+
 fn main() {
-    color_backtrace::install();
-    setup_logger(2);
-    let (events_loop, app) = window_mgmt::init_window();
-    window_mgmt::main_window_loop(events_loop, app);
+    let args = cli().get_matches();
+    let verbosity = args.occurrences_of("verbose");
+    setup_logger(verbosity);
+    setup_panic_hook(verbosity);
+    if args.is_present("update-hero-ids") {
+        let path = args.value_of("database").unwrap();
+        match update_hero_ids(path) {
+            Ok(updated) => println!(
+                "updated {} plays and {} enemy plays in {}",
+                updated.plays, updated.enemy_plays, path
+            ),
+            Err(e) => {
+                error!("could not update hero ids in {}: {}", path, e);
+                std::process::exit(1);
+            },
+        }
+        return;
+    }
+    let startup = match startup_events(&args) {
+        Ok(events) => events,
+        Err(e) => {
+            error!("could not load event script: {}", e);
+            std::process::exit(1);
+        },
+    };
+    let (events_loop, app) = match window_mgmt::init_window() {
+        Ok(window) => window,
+        Err(e) => {
+            error!("could not open the window: {}", e);
+            std::process::exit(1);
+        },
+    };
+    window_mgmt::main_window_loop(events_loop, app, startup);
+}
+
+/// Backtraces are for debugging; normally a panic is logged as a single line and the
+/// window shows the error screen.
+fn setup_panic_hook(verbosity: u64) {
+    if verbosity > 1 {
+        color_backtrace::install();
+    } else {
+        std::panic::set_hook(Box::new(|info| error!("{}", info)));
+    }
+}
+
+fn cli() -> App<'static, 'static> {
+    App::new("manager-gui")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Record and review Overwatch 3v3 Elimination matches")
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .multiple(true)
+                .help("Log more, repeat for more detail"),
+        )
+        .arg(
+            Arg::with_name("database")
+                .short("d")
+                .long("database")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("Open this match database instead of showing the start screen"),
+        )
+        .arg(
+            Arg::with_name("screen")
+                .long("screen")
+                .takes_value(true)
+                .possible_values(&["roster", "match"])
+                .requires("database")
+                .help("The screen to start on once the database is open"),
+        )
+        .arg(
+            Arg::with_name("roster")
+                .long("roster")
+                .value_name("BATTLETAG")
+                .takes_value(true)
+                .number_of_values(3)
+                .required_if("screen", "match")
+                .help("The three players to start a match with"),
+        )
+        .arg(
+            Arg::with_name("replay")
+                .long("replay")
+                .value_name("FILE")
+                .takes_value(true)
+                .help("Replay the UI events in FILE after startup, one per line"),
+        )
+        .arg(
+            Arg::with_name("update-hero-ids")
+                .long("update-hero-ids")
+                .requires("database")
+                .conflicts_with_all(&["screen", "replay"])
+                .help("Rewrite heroes stored under a former id, like mccree, and exit"),
+        )
+}
+
+/// Stored plays of renamed heroes load either way; this just keeps the database current.
+fn update_hero_ids(
+    path: &str,
+) -> Result<match_history::HeroIdUpdates, match_history::MatchDbError> {
+    let mut db = match_history::open_existing(path)?;
+    db.update_hero_ids()
+}
+
+/// Turn the arguments into the UI events that get the app to the requested screen.
+fn startup_events(args: &ArgMatches) -> Result<Vec<UiEvent>, script::ScriptError> {
+    let mut events = Vec::new();
+    if let Some(path) = args.value_of("database") {
+        events.push(UiEvent::OpenDatabase(path.into()));
+    }
+    if args.value_of("screen") == Some("match") {
+        for battletag in args.values_of("roster").into_iter().flatten() {
+            events.push(UiEvent::RecordBattletag(battletag.into()));
+            events.push(UiEvent::EnterBattleTag);
+        }
+        events.push(UiEvent::RosterPlay);
+    }
+    if let Some(path) = args.value_of("replay") {
+        events.extend(script::load(Path::new(path))?);
+    }
+    Ok(events)
 }
 
 fn setup_logger(level: u64) {
@@ -0,0 +1,98 @@
+use match_history::MatchDbError;
+use overwatch::overwatch_3v3::MatchHistoryError;
+use overwatch::ParseBattleTagError;
+use std::fmt;
+
+/// How many notifications are kept before the oldest are dropped.
+const NOTIFICATION_LIMIT: usize = 10;
+
+/// A problem handling a UI event that left the app usable.
+#[derive(Debug)]
+pub enum UiError {
+    History(MatchHistoryError),
+    Database(MatchDbError),
+    InvalidBattletag(ParseBattleTagError),
+    /// An event arrived on a screen that can't handle it; the screen is left as it was.
+    InvalidTransition(&'static str),
+}
+
+impl fmt::Display for UiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UiError::History(e) => write!(f, "{}", e),
+            UiError::Database(e) => write!(f, "{}", e),
+            UiError::InvalidBattletag(e) => write!(f, "{}", e),
+            UiError::InvalidTransition(transition) => {
+                write!(f, "can't go from {} on this screen", transition)
+            },
+        }
+    }
+}
+
+impl std::error::Error for UiError {}
+
+impl From<MatchHistoryError> for UiError {
+    fn from(e: MatchHistoryError) -> UiError {
+        UiError::History(e)
+    }
+}
+
+impl From<ParseBattleTagError> for UiError {
+    fn from(e: ParseBattleTagError) -> UiError {
+        UiError::InvalidBattletag(e)
+    }
+}
+
+impl From<MatchDbError> for UiError {
+    fn from(e: MatchDbError) -> UiError {
+        UiError::Database(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Messages for the header banner, newest last, kept until dismissed.
+#[derive(Debug, Default)]
+pub struct Notifications {
+    pending: Vec<Notification>,
+}
+
+impl Notifications {
+    pub fn warn<M: Into<String>>(&mut self, message: M) {
+        self.push(Severity::Warning, message.into())
+    }
+
+    pub fn error(&mut self, error: UiError) {
+        error!("{}", error);
+        self.push(Severity::Error, error.to_string())
+    }
+
+    fn push(&mut self, severity: Severity, message: String) {
+        self.pending.push(Notification { severity, message });
+        if self.pending.len() > NOTIFICATION_LIMIT {
+            self.pending.remove(0);
+        }
+    }
+
+    pub fn latest(&self) -> Option<&Notification> {
+        self.pending.last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn dismiss_latest(&mut self) {
+        self.pending.pop();
+    }
+}
@@ -0,0 +1,77 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// How many databases the start screen remembers.
+const RECENT_LIMIT: usize = 5;
+
+/// Databases opened before, most recent first, kept in a plain file with one path per line.
+#[derive(Debug, Default)]
+pub struct RecentDatabases {
+    paths: Vec<PathBuf>,
+}
+
+impl RecentDatabases {
+    /// Read the list, treating a missing or unreadable file as an empty one.
+    pub fn load() -> RecentDatabases {
+        let file = match recent_file() {
+            Some(file) => file,
+            None => return RecentDatabases::default(),
+        };
+        match fs::read_to_string(&file) {
+            Ok(contents) => RecentDatabases {
+                paths: contents
+                    .lines()
+                    .filter(|l| !l.is_empty())
+                    .map(PathBuf::from)
+                    .take(RECENT_LIMIT)
+                    .collect(),
+            },
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => RecentDatabases::default(),
+            Err(e) => {
+                warn!("could not read recent databases from {}: {}", file.display(), e);
+                RecentDatabases::default()
+            },
+        }
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Move `path` to the front of the list and write it out.
+    pub fn add(&mut self, path: PathBuf) {
+        let path = path.canonicalize().unwrap_or(path);
+        self.paths.retain(|p| *p != path);
+        self.paths.insert(0, path);
+        self.paths.truncate(RECENT_LIMIT);
+        if let Err(e) = self.save() {
+            warn!("could not save recent databases: {}", e);
+        }
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let file = match recent_file() {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = self
+            .paths
+            .iter()
+            .map(|p| format!("{}\n", p.display()))
+            .collect::<String>();
+        fs::write(file, contents)
+    }
+}
+
+fn recent_file() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(config.join("ow3v3-manager").join("recent-databases"))
+}
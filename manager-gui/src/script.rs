@@ -0,0 +1,175 @@
+use crate::state::UiEvent;
+use overwatch::overwatch_3v3::Player;
+use overwatch::{Hero, Map};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A line of an event script that could not be understood.
+#[derive(Debug)]
+pub struct ScriptError {
+    line: usize,
+    message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// Read a file of UI events to replay at startup.
+///
+/// Each line is a command followed by its argument, blank lines and lines starting
+/// with `#` are skipped:
+///
+/// ```text
+/// open-database ow3v3.dat
+/// add-battletag player1
+/// play
+/// select-hero widowmaker
+/// toggle-outcome
+/// record-round
+/// ```
+pub fn load(path: &Path) -> Result<Vec<UiEvent>, ScriptError> {
+    let contents = fs::read_to_string(path).map_err(|e| ScriptError {
+        line: 0,
+        message: format!("could not read {}: {}", path.display(), e),
+    })?;
+    parse(&contents)
+}
+
+pub fn parse(script: &str) -> Result<Vec<UiEvent>, ScriptError> {
+    let mut events = Vec::new();
+    for (idx, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (command, arg) = match line.find(char::is_whitespace) {
+            Some(split) => (&line[..split], line[split..].trim()),
+            None => (line, ""),
+        };
+        parse_command(command, arg, &mut events).map_err(|message| ScriptError {
+            line: idx + 1,
+            message,
+        })?;
+    }
+    Ok(events)
+}
+
+fn parse_command(command: &str, arg: &str, events: &mut Vec<UiEvent>) -> Result<(), String> {
+    let event = match command {
+        "open-database" => UiEvent::OpenDatabase(required(arg)?.into()),
+        "create-database" => UiEvent::CreateDatabase(required(arg)?.into()),
+        "add-battletag" => {
+            events.push(UiEvent::RecordBattletag(required(arg)?.into()));
+            UiEvent::EnterBattleTag
+        },
+        "save-team" => {
+            events.push(UiEvent::RecordTeamName(required(arg)?.into()));
+            UiEvent::SaveTeam
+        },
+        "remove-player" => UiEvent::RemoveFromRoster(player(arg)?),
+        "play" => UiEvent::RosterPlay,
+        "select-player" => UiEvent::RoundSelectPlayer(player(arg)?),
+        "select-enemy" => UiEvent::RoundSelectEnemy(player(arg)?),
+        "select-hero" => UiEvent::RoundSelectHero(parse_arg::<Hero>(arg)?),
+        "toggle-outcome" => UiEvent::RoundToggleOutcome,
+        "select-map" => UiEvent::MatchSelectMap(match required(arg)? {
+            "none" => None,
+            key => Some(parse_arg::<Map>(key)?),
+        }),
+        "record-round" => UiEvent::RoundRecord,
+        "undo" => UiEvent::Undo,
+        "redo" => UiEvent::Redo,
+        _ => return Err(format!("unknown command '{}'", command)),
+    };
+    events.push(event);
+    Ok(())
+}
+
+fn required(arg: &str) -> Result<&str, String> {
+    if arg.is_empty() {
+        Err("missing argument".into())
+    } else {
+        Ok(arg)
+    }
+}
+
+fn parse_arg<T>(arg: &str) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    T::from_str(required(arg)?).map_err(|e| e.to_string())
+}
+
+/// Players are numbered from one, as on screen.
+fn player(arg: &str) -> Result<Player, String> {
+    let n = parse_arg::<usize>(arg)?;
+    Player::iter()
+        .find(|p| p.numeric() == n)
+        .ok_or_else(|| format!("no player {}, expected 1 to 3", n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn parse_script() {
+        let script = "\
+# set up a match
+open-database ow3v3.dat
+add-battletag Player1#1234
+
+  play
+select-player 2
+select-hero  mei
+select-map none
+select-map petra
+toggle-outcome
+record-round
+";
+        assert_eq!(
+            parse(script).unwrap(),
+            vec![
+                UiEvent::OpenDatabase(PathBuf::from("ow3v3.dat")),
+                UiEvent::RecordBattletag("Player1#1234".into()),
+                UiEvent::EnterBattleTag,
+                UiEvent::RosterPlay,
+                UiEvent::RoundSelectPlayer(Player::Two),
+                UiEvent::RoundSelectHero(Hero::Mei),
+                UiEvent::MatchSelectMap(None),
+                UiEvent::MatchSelectMap(Some(Map::Petra)),
+                UiEvent::RoundToggleOutcome,
+                UiEvent::RoundRecord,
+            ]
+        );
+    }
+
+    #[test]
+    fn only_comments_and_blank_lines() {
+        assert_eq!(parse("# nothing\n\n   \n").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn error_names_the_bad_line() {
+        for (script, line, message) in &[
+            ("play\n\n# comment\nselect-hero nobody\n", 4, "nobody"),
+            ("play\ndance\n", 2, "unknown command 'dance'"),
+            ("select-player 4\n", 1, "no player 4, expected 1 to 3"),
+            ("play\nopen-database\n", 2, "missing argument"),
+        ] {
+            let e = parse(script).unwrap_err();
+            assert_eq!(e.line, *line, "{}", script);
+            assert!(e.to_string().starts_with(&format!("line {}: ", line)));
+            assert!(e.message.contains(message), "{}: {}", script, e);
+        }
+    }
+}
@@ -1,20 +1,160 @@
-use overwatch::overwatch_3v3::{CompBuilder, Match, Player, Roster, Round};
-use overwatch::{BattleTag, Hero, HeroPool};
+use overwatch::overwatch_3v3::{
+    plan, CompBuilder, Match, MatchHistoryError, Plan, Player, Recommender, Roster, Round,
+    RoundOutcome, Suggestion, Team, DEFAULT_BREADTH,
+};
+use overwatch::{BattleTag, Hero, HeroPool, Map};
+use std::borrow::Cow;
 use std::mem;
-use match_history::{MatchDb, MatchDbError, open};
+use crate::notify::{Notifications, UiError};
+use crate::recent::RecentDatabases;
+use match_history::{create, open_existing, HistoryScorer, MatchDb, MatchDbError};
+use match_history::Team as SavedTeam;
 use std::path::PathBuf;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum UiEvent {
+    RecordDatabasePath(String),
     OpenDatabase(PathBuf),
+    CreateDatabase(PathBuf),
     RecordBattletag(String),
     EnterBattleTag,
+    HighlightNextSuggestion,
+    HighlightPreviousSuggestion,
+    PickSuggestion(usize),
     RemoveFromRoster(Player),
+    RecordTeamName(String),
+    LoadTeam(usize),
+    SaveTeam,
+    DeleteTeam(usize),
     RosterPlay,
     RoundSelectPlayer(Player),
+    RoundSelectEnemy(Player),
     RoundSelectHero(Hero),
+    RoundSelectComp([Hero; 3]),
     RoundToggleOutcome,
+    MatchSelectMap(Option<Map>),
     RoundRecord,
+    MatchSelectRound(usize),
+    MatchEditRound,
+    MatchCancelEdit,
+    MatchMoveRoundUp,
+    MatchDeleteRound,
+    RetrySaveMatch,
+    Undo,
+    Redo,
+    Quit,
+}
+
+impl UiEvent {
+    /// Whether the change this event makes can be taken back with `Undo`.
+    ///
+    /// Opening a database, starting a match and saving one move between screens, and
+    /// each screen keeps its own undo history. Saved teams live in the database, so
+    /// saving or deleting one is not undone either.
+    fn is_undoable(&self) -> bool {
+        match self {
+            UiEvent::EnterBattleTag
+            | UiEvent::PickSuggestion(_)
+            | UiEvent::RemoveFromRoster(_)
+            | UiEvent::LoadTeam(_)
+            | UiEvent::RoundSelectPlayer(_)
+            | UiEvent::RoundSelectEnemy(_)
+            | UiEvent::RoundSelectHero(_)
+            | UiEvent::RoundSelectComp(_)
+            | UiEvent::RoundToggleOutcome
+            | UiEvent::MatchSelectMap(_)
+            | UiEvent::RoundRecord
+            | UiEvent::MatchEditRound
+            | UiEvent::MatchCancelEdit
+            | UiEvent::MatchMoveRoundUp
+            | UiEvent::MatchDeleteRound => true,
+            UiEvent::RecordDatabasePath(_)
+            | UiEvent::OpenDatabase(_)
+            | UiEvent::CreateDatabase(_)
+            | UiEvent::RecordBattletag(_)
+            | UiEvent::HighlightNextSuggestion
+            | UiEvent::HighlightPreviousSuggestion
+            | UiEvent::RecordTeamName(_)
+            | UiEvent::SaveTeam
+            | UiEvent::DeleteTeam(_)
+            | UiEvent::RosterPlay
+            | UiEvent::MatchSelectRound(_)
+            | UiEvent::RetrySaveMatch
+            | UiEvent::Undo
+            | UiEvent::Redo
+            | UiEvent::Quit => false,
+        }
+    }
+}
+
+/// How many steps back `Undo` can go on one screen.
+const UNDO_LIMIT: usize = 100;
+
+#[derive(Debug)]
+struct UndoHistory<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+}
+
+impl<T> Default for UndoHistory<T> {
+    fn default() -> UndoHistory<T> {
+        UndoHistory {
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+}
+
+impl<T> UndoHistory<T> {
+    fn push(&mut self, before: T) {
+        if self.undo.len() == UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(before);
+        self.redo.clear();
+    }
+
+    fn undo(&mut self, current: T) -> Option<T> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    fn redo(&mut self, current: T) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+}
+
+/// A screen whose changes can be undone by restoring snapshots of it.
+trait Undoable {
+    type Snapshot: PartialEq;
+
+    fn snapshot(&self) -> Self::Snapshot;
+    fn restore(&mut self, snapshot: Self::Snapshot);
+    fn undo_history(&mut self) -> &mut UndoHistory<Self::Snapshot>;
+
+    /// Keep `before` as an undo step, unless nothing changed since.
+    fn record(&mut self, before: Self::Snapshot) {
+        if before != self.snapshot() {
+            self.undo_history().push(before)
+        }
+    }
+
+    fn undo(&mut self) {
+        let current = self.snapshot();
+        if let Some(previous) = self.undo_history().undo(current) {
+            self.restore(previous)
+        }
+    }
+
+    fn redo(&mut self) {
+        let current = self.snapshot();
+        if let Some(next) = self.undo_history().redo(current) {
+            self.restore(next)
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -22,24 +162,39 @@ pub enum State {
     Dummy,
     LoadDatabase(LoadDbState),
     RosterSelect(RosterSelectState, MatchDb),
-    Match(MatchState, MatchDb),
+    Match(Box<MatchState>, MatchDb),
+    /// Something went wrong that the app can't recover from; all that is left is to quit.
+    Fatal(String),
     Exit,
 }
 
-#[derive(Debug)]
-pub enum LoadDbState {
-    NotReady,
-    Ready(MatchDb),
-    Failure(MatchDbError),
+#[derive(Debug, Default)]
+pub struct LoadDbState {
+    pub path: String,
+    recent: RecentDatabases,
 }
 
 impl LoadDbState {
-    fn open(&mut self, path: PathBuf) {
-        let mut new = match open(&path) {
-            Ok(mdb) =>  LoadDbState::Ready(mdb),
-            Err(e) => LoadDbState::Failure(e),
+    fn new() -> LoadDbState {
+        LoadDbState {
+            recent: RecentDatabases::load(),
+            ..LoadDbState::default()
+        }
+    }
+
+    pub fn recent(&self) -> &[PathBuf] {
+        self.recent.paths()
+    }
+
+    fn open(&mut self, path: PathBuf, create_new: bool) -> Result<MatchDb, MatchDbError> {
+        let result = if create_new {
+            create(&path)
+        } else {
+            open_existing(&path)
         };
-        mem::swap(self, &mut new)
+        let db = result?;
+        self.recent.add(path);
+        Ok(db)
     }
 }
 
@@ -47,42 +202,305 @@ impl LoadDbState {
 #[derive(Debug, Default)]
 pub struct RosterSelectState {
     pub battletag: String,
-    pub roster: Vec<String>,
+    pub roster: Vec<BattleTag>,
+    /// Known battletags matching what has been typed, best first.
+    suggestions: Vec<BattleTag>,
+    highlighted: Option<usize>,
+    /// What to save the current roster as.
+    pub team_name: String,
+    teams: Vec<SavedTeam>,
+    undo: UndoHistory<(String, Vec<BattleTag>)>,
+}
+
+/// How many battletags the autocomplete dropdown shows.
+const BATTLETAG_SUGGESTIONS: usize = 5;
+
+impl Undoable for RosterSelectState {
+    type Snapshot = (String, Vec<BattleTag>);
+
+    fn snapshot(&self) -> Self::Snapshot {
+        (self.battletag.clone(), self.roster.clone())
+    }
+
+    fn restore(&mut self, (battletag, roster): Self::Snapshot) {
+        self.battletag = battletag;
+        self.roster = roster;
+        self.suggestions.clear();
+        self.highlighted = None;
+    }
+
+    fn undo_history(&mut self) -> &mut UndoHistory<Self::Snapshot> {
+        &mut self.undo
+    }
 }
 
 impl RosterSelectState {
+    fn new(roster: Vec<BattleTag>) -> RosterSelectState {
+        RosterSelectState {
+            roster,
+            ..RosterSelectState::default()
+        }
+    }
+
     pub fn get_battletag(&self, player: Player) -> Option<&str> {
-        self.roster.get(player.index()).map(|s| s.as_str())
+        self.roster.get(player.index()).map(|bt| bt.as_str())
     }
     #[inline]
     pub fn ready_to_play(&self) -> bool {
         self.roster.len() == 3
     }
-    fn into_ow_roster(self) -> Roster {
-        assert_eq!(self.roster.len(), 3);
-        let mut players = self.roster.into_iter();
-        Roster::new(
-            players.next().unwrap(),
-            players.next().unwrap(),
-            players.next().unwrap(),
-        )
+
+    pub fn suggestions(&self) -> &[BattleTag] {
+        &self.suggestions
+    }
+
+    pub fn highlighted(&self) -> Option<usize> {
+        self.highlighted
+    }
+
+    fn refresh_suggestions(&mut self, db: &MatchDb) -> Result<(), MatchDbError> {
+        self.highlighted = None;
+        self.suggestions.clear();
+        if self.battletag.trim().is_empty() {
+            return Ok(());
+        }
+        let found = db.suggest_battletags(self.battletag.trim(), BATTLETAG_SUGGESTIONS + 3)?;
+        let roster = &self.roster;
+        self.suggestions = found
+            .into_iter()
+            .filter(|bt| !roster.contains(bt))
+            .take(BATTLETAG_SUGGESTIONS)
+            .collect();
+        Ok(())
+    }
+
+    /// Move the highlight down or up the suggestions, wrapping around through no highlight.
+    fn move_highlight(&mut self, forward: bool) {
+        let len = self.suggestions.len();
+        if len == 0 {
+            return;
+        }
+        self.highlighted = match (self.highlighted, forward) {
+            (None, true) => Some(0),
+            (None, false) => Some(len - 1),
+            (Some(idx), true) if idx + 1 < len => Some(idx + 1),
+            (Some(idx), false) if idx > 0 => Some(idx - 1),
+            (Some(_), _) => None,
+        };
+    }
+
+    pub fn teams(&self) -> &[SavedTeam] {
+        &self.teams
+    }
+
+    /// The saved team made up of exactly the players on the roster, in any order.
+    pub fn selected_team(&self) -> Option<&SavedTeam> {
+        if !self.ready_to_play() {
+            return None;
+        }
+        self.teams.iter().find(|team| {
+            let Roster(p1, p2, p3) = &team.roster;
+            [p1, p2, p3].iter().all(|bt| self.roster.contains(bt))
+        })
+    }
+
+    fn refresh_teams(&mut self, db: &MatchDb) -> Result<(), MatchDbError> {
+        self.teams = db.list_teams()?;
+        Ok(())
+    }
+
+    fn load_team(&mut self, idx: usize) {
+        let team = match self.teams.get(idx) {
+            Some(team) => team,
+            None => return,
+        };
+        let Roster(p1, p2, p3) = &team.roster;
+        self.roster = vec![p1.clone(), p2.clone(), p3.clone()];
+        self.team_name = team.name.clone();
+        self.battletag.clear();
+        self.suggestions.clear();
+        self.highlighted = None;
+    }
+
+    /// Save the roster under `team_name`, replacing the players of a team already called that.
+    fn save_team(&mut self, db: &mut MatchDb) -> Result<(), MatchDbError> {
+        let name = self.team_name.trim().to_string();
+        let roster = match self.ow_roster() {
+            Some(roster) if !name.is_empty() => roster,
+            _ => return Ok(()),
+        };
+        match self.teams.iter().find(|t| t.name == name) {
+            Some(team) => db.update_team(team.id, &name, &roster)?,
+            None => db.create_team(&name, &roster)?,
+        };
+        self.refresh_teams(db)
+    }
+
+    fn delete_team(&mut self, idx: usize, db: &mut MatchDb) -> Result<(), MatchDbError> {
+        if let Some(team) = self.teams.get(idx) {
+            db.delete_team(team.id)?;
+        }
+        self.refresh_teams(db)
+    }
+
+    /// Add a battletag to the roster, registering it with the database if it is new.
+    ///
+    /// Battletags already known are taken as they are, keeping the spelling they were stored
+    /// with unless that was lost to lowercasing; new ones must parse.
+    fn enter_battletag(&mut self, battletag: String, db: &MatchDb) -> Result<(), UiError> {
+        if battletag.trim().is_empty() || self.roster.len() >= 3 {
+            return Ok(());
+        }
+        let typed = BattleTag::new(battletag.trim());
+        let battletag = match self.suggestions.iter().find(|bt| **bt == typed) {
+            Some(known) if known.as_str() != known.key() => known.clone(),
+            Some(_) => typed,
+            None => BattleTag::parse(&battletag)?,
+        };
+        db.get_or_insert_battletag_id(&battletag)?;
+        // An alias stands for its player, whose history the suggestions are scored on
+        let battletag = db.canonical_battletag(&battletag)?.unwrap_or(battletag);
+        if self.roster.contains(&battletag) {
+            return Ok(());
+        }
+        self.roster.push(battletag);
+        self.battletag.clear();
+        self.suggestions.clear();
+        self.highlighted = None;
+        Ok(())
+    }
+
+    /// The roster to play with, once all three players are in.
+    fn ow_roster(&self) -> Option<Roster> {
+        match self.roster.as_slice() {
+            [p1, p2, p3] => Some(Roster(p1.clone(), p2.clone(), p3.clone())),
+            _ => None,
+        }
     }
 }
 
 #[derive(Debug, Default)]
 pub struct MatchState {
+    /// Whose slot the hero grid fills; `selected_player` doubles as the enemy slot.
+    selected_team: Team,
     selected_player: Player,
     builder: CompBuilder,
     history: Match,
-    used_heros: HeroPool,
+    /// The recorded round the match history panel has selected.
+    selected_round: Option<usize>,
+    /// The recorded round loaded into `builder`, which submitting replaces.
+    editing: Option<usize>,
+    /// The match is over but could not be written to the database.
+    save_failed: bool,
+    /// The saved team playing, which the match is recorded under.
+    team_id: Option<i64>,
+    scorer: HistoryScorer,
+    recommender: Recommender,
+    suggestions: Vec<Suggestion>,
+    plan: Plan,
+    undo: UndoHistory<MatchSnapshot>,
 }
 
+/// The parts of a `MatchState` the user edits, everything else is derived from them.
+#[derive(Debug, Clone, PartialEq)]
+struct MatchSnapshot {
+    selected_team: Team,
+    selected_player: Player,
+    builder: CompBuilder,
+    history: Match,
+    selected_round: Option<usize>,
+    editing: Option<usize>,
+}
+
+impl Undoable for MatchState {
+    type Snapshot = MatchSnapshot;
+
+    fn snapshot(&self) -> MatchSnapshot {
+        MatchSnapshot {
+            selected_team: self.selected_team,
+            selected_player: self.selected_player,
+            builder: self.builder.clone(),
+            history: self.history.clone(),
+            selected_round: self.selected_round,
+            editing: self.editing,
+        }
+    }
+
+    fn restore(&mut self, snapshot: MatchSnapshot) {
+        let MatchSnapshot {
+            selected_team,
+            selected_player,
+            builder,
+            history,
+            selected_round,
+            editing,
+        } = snapshot;
+        self.selected_team = selected_team;
+        self.selected_player = selected_player;
+        self.builder = builder;
+        self.history = history;
+        self.selected_round = selected_round;
+        self.editing = editing;
+        self.refresh_suggestions();
+        self.refresh_plan();
+    }
+
+    fn undo_history(&mut self) -> &mut UndoHistory<MatchSnapshot> {
+        &mut self.undo
+    }
+}
+
+/// How many comps the suggestion strip has room for.
+pub const SUGGESTION_COUNT: usize = 3;
+
 impl MatchState {
-    fn new(roster: Roster) -> MatchState {
-        MatchState {
+    fn new(roster: Roster, team_id: Option<i64>, scorer: HistoryScorer) -> MatchState {
+        let mut state = MatchState {
             builder: CompBuilder::new(roster),
+            team_id,
+            scorer,
             ..MatchState::default()
-        }
+        };
+        state.refresh_suggestions();
+        state.refresh_plan();
+        state
+    }
+
+    pub fn suggestions(&self) -> &[Suggestion] {
+        &self.suggestions
+    }
+
+    pub fn plan(&self) -> &Plan {
+        &self.plan
+    }
+
+    /// Only depends on the recorded rounds, so unlike suggestions this is refreshed per round.
+    fn refresh_plan(&mut self) {
+        self.plan = plan(&self.scorer, &self.history, &self.builder, DEFAULT_BREADTH);
+    }
+
+    fn refresh_suggestions(&mut self) {
+        let history = open_history(&self.history, self.editing);
+        self.suggestions = self.recommender.recommend(
+            &self.scorer,
+            &history,
+            &self.builder,
+            SUGGESTION_COUNT,
+        );
+    }
+
+    fn open_history(&self) -> Cow<'_, Match> {
+        open_history(&self.history, self.editing)
+    }
+
+    #[inline]
+    pub fn selected_round(&self) -> Option<usize> {
+        self.selected_round
+    }
+
+    #[inline]
+    pub fn editing(&self) -> Option<usize> {
+        self.editing
     }
 
     pub fn match_len(&self) -> usize {
@@ -93,9 +511,24 @@ impl MatchState {
         self.history.iter()
     }
 
+    /// The heroes the hero grid can still pick for the selected slot.
+    pub fn selectable_heros(&self) -> HeroPool {
+        let history = self.open_history();
+        match self.selected_team {
+            Team::Ally => self.builder.available_heros(&history, self.selected_player),
+            Team::Enemy => self
+                .builder
+                .available_enemy_heros(&history, self.selected_player),
+        }
+    }
+
+    /// The enemy slot the hero grid is picking for, if any.
     #[inline]
-    pub fn is_used(&self, hero: Hero) -> bool {
-        self.used_heros.contains(&hero)
+    pub fn selected_enemy(&self) -> Option<Player> {
+        match self.selected_team {
+            Team::Ally => None,
+            Team::Enemy => Some(self.selected_player),
+        }
     }
 
     #[inline]
@@ -109,13 +542,18 @@ impl MatchState {
     }
 
     #[inline]
-    pub fn get_selected_player(&self) -> Player {
-        self.selected_player
+    pub fn get_enemy_hero(&self, slot: Player) -> Option<Hero> {
+        self.builder.get_enemy(slot)
     }
 
     #[inline]
-    pub fn get_win(&self) -> Option<bool> {
-        self.builder.get_win()
+    pub fn get_outcome(&self) -> Option<RoundOutcome> {
+        self.builder.get_outcome()
+    }
+
+    #[inline]
+    pub fn map(&self) -> Option<Map> {
+        self.history.map()
     }
 
     #[inline]
@@ -123,109 +561,285 @@ impl MatchState {
         self.builder.validate()
     }
 
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.history.match_outcome().is_some()
+    }
+
+    /// New rounds stop once the match is over, but a recorded round can still be replaced,
+    /// say to fix the match after saving it failed.
+    #[inline]
+    pub fn accepts_round(&self) -> bool {
+        !self.is_finished() || self.editing.is_some()
+    }
+
+    /// Whether the last attempt to save the finished match failed.
+    #[inline]
+    pub fn save_failed(&self) -> bool {
+        self.save_failed
+    }
+
     fn clear_hero_selection(&mut self, player: Player) {
-        if let Some(hero) = self.builder.get_hero(player) {
-            self.used_heros.remove(&hero);
+        if self.builder.get_hero(player).is_some() {
             self.builder.clear_hero(player);
+            self.refresh_suggestions();
         }
     }
 
+    fn clear_enemy_selection(&mut self, slot: Player) {
+        self.builder.clear_enemy(slot);
+    }
+
     fn select_hero(&mut self, hero: Hero) {
-        if self.used_heros.contains(&hero) {
+        if !self.selectable_heros().contains(&hero) {
             return;
         }
-        self.clear_hero_selection(self.selected_player);
-        self.builder.set_player(self.selected_player, hero);
-        self.used_heros.insert(hero);
+        match self.selected_team {
+            Team::Ally => {
+                self.clear_hero_selection(self.selected_player);
+                self.builder.set_player(self.selected_player, hero);
+                self.refresh_suggestions();
+            },
+            Team::Enemy => {
+                self.clear_enemy_selection(self.selected_player);
+                self.builder.set_enemy(self.selected_player, hero);
+            },
+        }
         self.selected_player = self.selected_player.cycle_next();
     }
+
+    /// Fill every player slot at once, as long as the whole comp is still available.
+    fn select_comp(&mut self, comp: [Hero; 3]) {
+        for player in Player::iter() {
+            self.clear_hero_selection(player);
+        }
+        if !self.builder.is_legal_comp(&self.open_history(), comp) {
+            return;
+        }
+        for player in Player::iter() {
+            self.builder.set_player(player, comp[player.index()]);
+        }
+        self.selected_team = Team::Ally;
+        self.selected_player = Player::One;
+        self.refresh_suggestions();
+    }
+
+    /// Start over with an empty builder for the next round.
+    fn reset_builder(&mut self) {
+        self.builder = self.builder.next_round();
+        self.editing = None;
+        self.selected_team = Team::Ally;
+        self.selected_player = Player::One;
+        self.refresh_suggestions();
+    }
+
+    fn edit_round(&mut self) {
+        let round = match self.selected_round.and_then(|idx| self.history.get_round(idx)) {
+            Some(round) => round.clone(),
+            None => return,
+        };
+        self.builder = CompBuilder::from(round);
+        self.editing = self.selected_round;
+        self.selected_team = Team::Ally;
+        self.selected_player = Player::One;
+        self.refresh_suggestions();
+    }
+
+    /// Apply a change to the recorded rounds, leaving them untouched if it was refused.
+    fn change_rounds<F>(&mut self, change: F) -> Result<(), MatchHistoryError>
+    where
+        F: FnOnce(&mut Match) -> Result<(), MatchHistoryError>,
+    {
+        change(&mut self.history)?;
+        self.refresh_plan();
+        Ok(())
+    }
+}
+
+/// The rounds the builder has to fit in with, leaving out a round being edited.
+fn open_history(history: &Match, editing: Option<usize>) -> Cow<'_, Match> {
+    match editing {
+        Some(idx) => {
+            let mut history = history.clone();
+            // removing a round can't invalidate the ones after it
+            let _ = history.remove_round(idx);
+            Cow::Owned(history)
+        },
+        None => Cow::Borrowed(history),
+    }
 }
 
 impl State {
     pub fn new() -> State {
-        State::LoadDatabase(LoadDbState::NotReady)
+        State::LoadDatabase(LoadDbState::new())
     }
 
-    pub fn transform(&mut self, updates: impl Iterator<Item=UiEvent>) {
+    /// Apply every event in turn, reporting the ones that failed to `notifications`.
+    pub fn transform(
+        &mut self,
+        updates: impl Iterator<Item=UiEvent>,
+        notifications: &mut Notifications,
+    ) {
         for e in updates {
-            self.event(e)
+            if let Err(e) = self.event(e) {
+                notifications.error(e)
+            }
         }
     }
 
-    fn transition_loaddb_roster(&mut self) {
-        let mut state = State::Dummy;
-        mem::swap(&mut state, self);
-        let db = match state {
-            State::LoadDatabase( LoadDbState::Ready(db)) =>  db,
-            s => panic!(
-                "attempted invalid state transition from match -> roster: {:#?}",
-                s
-            ),
-        };
-
-        state = State::RosterSelect(RosterSelectState {
-            battletag: "".to_string(),
-            roster: Vec::new(),
-        }, db);
-        mem::swap(&mut state, self);
+    fn transition_loaddb_roster(&mut self, db: MatchDb) -> Result<(), UiError> {
+        let mut roster_state = RosterSelectState::new(Vec::new());
+        let teams = roster_state.refresh_teams(&db);
+        *self = State::RosterSelect(roster_state, db);
+        Ok(teams?)
     }
 
-    fn transition_roster_match(&mut self) {
+    fn transition_roster_match(&mut self) -> Result<(), UiError> {
         let mut state = State::Dummy;
         mem::swap(&mut state, self);
-        let (roster_state, db) = match state {
-            State::RosterSelect(r, db) => (r, db),
-            s => panic!(
-                "attempted invalid state transition from roster -> match: {:#?}",
-                s
-            ),
+        let (roster_state, db, roster) = match state {
+            State::RosterSelect(r, db) => match r.ow_roster() {
+                Some(roster) => (r, db, roster),
+                None => {
+                    *self = State::RosterSelect(r, db);
+                    return Ok(());
+                },
+            },
+            s => {
+                *self = s;
+                return Err(UiError::InvalidTransition("roster to match"));
+            },
         };
-        state = State::Match(MatchState::new(roster_state.into_ow_roster()), db);
-        mem::swap(&mut state, self);
+        let (scorer, scorer_error) = match db.comp_scorer() {
+            Ok(scorer) => (scorer, None),
+            Err(e) => (HistoryScorer::default(), Some(e)),
+        };
+        let team_id = roster_state.selected_team().map(|t| t.id);
+        let match_state = MatchState::new(roster, team_id, scorer);
+        *self = State::Match(Box::new(match_state), db);
+        match scorer_error {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
     }
 
-    fn transition_match_roster(&mut self) {
+    fn transition_match_roster(&mut self) -> Result<(), UiError> {
         let mut state = State::Dummy;
         mem::swap(&mut state, self);
         let (match_state, db) = match state {
             State::Match(m, db) => (m, db),
-            s => panic!(
-                "attempted invalid state transition from match -> roster: {:#?}",
-                s
-            ),
+            s => {
+                *self = s;
+                return Err(UiError::InvalidTransition("match to roster"));
+            },
         };
 
-        let MatchState { builder, ..} = match_state;
+        let MatchState { builder, ..} = *match_state;
 
         let Roster(p1, p2, p3) = builder.roster();
-        state = State::RosterSelect(RosterSelectState {
-            battletag: "".to_string(),
-            roster: vec![p1.into_inner(), p2.into_inner(), p3.into_inner()],
-        }, db);
-        mem::swap(&mut state, self);
+        let mut roster_state = RosterSelectState::new(vec![p1, p2, p3]);
+        let teams = roster_state.refresh_teams(&db);
+        *self = State::RosterSelect(roster_state, db);
+        Ok(teams?)
+    }
+
+    /// Write the finished match to the database, and return to roster selection.
+    ///
+    /// If the write fails we stay on the match screen with the failure recorded,
+    /// so the user can retry instead of losing the match.
+    fn save_match(&mut self) -> Result<(), UiError> {
+        if let State::Match(ref mut match_state, ref mut db) = self {
+            match db.record_team_match(&match_state.history, match_state.team_id) {
+                Ok(match_id) => {
+                    info!("recorded match {}", match_id);
+                    match_state.save_failed = false;
+                },
+                Err(e) => {
+                    match_state.save_failed = true;
+                    return Err(e.into());
+                },
+            }
+        }
+        self.transition_match_roster()
+    }
+
+    pub fn event(&mut self, event: UiEvent) -> Result<(), UiError> {
+        match (&event, &mut *self) {
+            (UiEvent::Undo, State::RosterSelect(roster_state, _)) => roster_state.undo(),
+            (UiEvent::Undo, State::Match(match_state, _)) => match_state.undo(),
+            (UiEvent::Redo, State::RosterSelect(roster_state, _)) => roster_state.redo(),
+            (UiEvent::Redo, State::Match(match_state, _)) => match_state.redo(),
+            (e, _) if !e.is_undoable() => return self.apply(event),
+            (_, State::RosterSelect(roster_state, _)) => {
+                let before = roster_state.snapshot();
+                let applied = self.apply(event);
+                if let State::RosterSelect(ref mut roster_state, _) = self {
+                    roster_state.record(before)
+                }
+                return applied;
+            },
+            (_, State::Match(match_state, _)) => {
+                let before = match_state.snapshot();
+                let applied = self.apply(event);
+                if let State::Match(ref mut match_state, _) = self {
+                    match_state.record(before)
+                }
+                return applied;
+            },
+            _ => return self.apply(event),
+        }
+        Ok(())
     }
 
-    pub fn event(&mut self, event: UiEvent) {
+    fn apply(&mut self, event: UiEvent) -> Result<(), UiError> {
         match event {
+            UiEvent::RecordDatabasePath(path) => {
+                if let State::LoadDatabase(ref mut load_db_state) = self {
+                    load_db_state.path = path;
+                }
+            }
             UiEvent::OpenDatabase(path) => {
                 if let State::LoadDatabase(ref mut load_db_state) = self {
-                    load_db_state.open(path);
-                    if let LoadDbState::Ready(db) = load_db_state {
-                        self.transition_loaddb_roster()
-                    }
+                    let db = load_db_state.open(path, false)?;
+                    self.transition_loaddb_roster(db)?
+                }
+            }
+            UiEvent::CreateDatabase(path) => {
+                if let State::LoadDatabase(ref mut load_db_state) = self {
+                    let db = load_db_state.open(path, true)?;
+                    self.transition_loaddb_roster(db)?
                 }
             }
             UiEvent::RecordBattletag(s) => {
-                if let State::RosterSelect(ref mut roster_state, _) = self {
+                if let State::RosterSelect(ref mut roster_state, ref db) = self {
                     roster_state.battletag = s;
+                    roster_state.refresh_suggestions(db)?;
                 }
             }
             UiEvent::EnterBattleTag => {
-                if let State::RosterSelect(ref mut roster_state,_) = self {
-                    if roster_state.roster.len() < 3 {
-                        let mut bt = String::new();
-                        mem::swap(&mut bt, &mut roster_state.battletag);
-                        roster_state.roster.push(bt);
+                if let State::RosterSelect(ref mut roster_state, ref db) = self {
+                    let bt = match roster_state.highlighted {
+                        Some(idx) => roster_state.suggestions[idx].as_str().to_string(),
+                        None => roster_state.battletag.clone(),
+                    };
+                    roster_state.enter_battletag(bt, db)?;
+                }
+            }
+            UiEvent::HighlightNextSuggestion => {
+                if let State::RosterSelect(ref mut roster_state, _) = self {
+                    roster_state.move_highlight(true)
+                }
+            }
+            UiEvent::HighlightPreviousSuggestion => {
+                if let State::RosterSelect(ref mut roster_state, _) = self {
+                    roster_state.move_highlight(false)
+                }
+            }
+            UiEvent::PickSuggestion(idx) => {
+                if let State::RosterSelect(ref mut roster_state, ref db) = self {
+                    if let Some(bt) = roster_state.suggestions.get(idx) {
+                        let bt = bt.as_str().to_string();
+                        roster_state.enter_battletag(bt, db)?;
                     }
                 }
             }
@@ -237,43 +851,314 @@ impl State {
                     }
                 }
             }
+            UiEvent::RecordTeamName(name) => {
+                if let State::RosterSelect(ref mut roster_state, _) = self {
+                    roster_state.team_name = name;
+                }
+            }
+            UiEvent::LoadTeam(idx) => {
+                if let State::RosterSelect(ref mut roster_state, _) = self {
+                    roster_state.load_team(idx)
+                }
+            }
+            UiEvent::SaveTeam => {
+                if let State::RosterSelect(ref mut roster_state, ref mut db) = self {
+                    roster_state.save_team(db)?
+                }
+            }
+            UiEvent::DeleteTeam(idx) => {
+                if let State::RosterSelect(ref mut roster_state, ref mut db) = self {
+                    roster_state.delete_team(idx, db)?
+                }
+            }
             UiEvent::RosterPlay => {
-                self.transition_roster_match();
+                self.transition_roster_match()?;
             }
             UiEvent::RoundSelectPlayer(p) => {
                 if let State::Match(ref mut match_state,_) = self {
+                    match_state.selected_team = Team::Ally;
                     match_state.selected_player = p;
                     match_state.clear_hero_selection(p);
                 }
             }
+            UiEvent::RoundSelectEnemy(p) => {
+                if let State::Match(ref mut match_state,_) = self {
+                    match_state.selected_team = Team::Enemy;
+                    match_state.selected_player = p;
+                    match_state.clear_enemy_selection(p);
+                }
+            }
             UiEvent::RoundSelectHero(h) => {
                 if let State::Match(ref mut match_state,_) = self {
                     match_state.select_hero(h)
                 }
             }
+            UiEvent::RoundSelectComp(comp) => {
+                if let State::Match(ref mut match_state,_) = self {
+                    match_state.select_comp(comp)
+                }
+            }
             UiEvent::RoundToggleOutcome => {
                 if let State::Match(ref mut match_state,_) = self {
-                    match_state
-                        .builder
-                        .set_win(match match_state.builder.get_win() {
-                            Some(true) => false,
-                            _ => true,
-                        })
+                    let next = match match_state.builder.get_outcome() {
+                        None | Some(RoundOutcome::Draw) => RoundOutcome::Win,
+                        Some(RoundOutcome::Win) => RoundOutcome::Loss,
+                        Some(RoundOutcome::Loss) => RoundOutcome::Draw,
+                    };
+                    match_state.builder.set_outcome(next)
+                }
+            }
+            UiEvent::MatchSelectMap(map) => {
+                if let State::Match(ref mut match_state,_) = self {
+                    if !match_state.is_finished() {
+                        match_state.history.set_map(map)
+                    }
                 }
             }
             UiEvent::RoundRecord => {
                 if let State::Match(ref mut match_state,_) = self {
-                    let mut builder = CompBuilder::new(match_state.builder.roster());
-                    mem::swap(&mut match_state.builder, &mut builder);
-                    let r = builder.finalize().unwrap();
-                    match_state.history.insert_round(r).unwrap();
-                    match_state.used_heros = match_state.history.used_heros();
-                    match_state.selected_player = Player::One;
-                    if match_state.history.match_outcome().is_some() {
-                        self.transition_match_roster()
+                    if !match_state.accepts_round() {
+                        return Ok(());
+                    }
+                    let round = match_state.builder.clone().finalize()?;
+                    let editing = match_state.editing;
+                    match_state.change_rounds(|history| match editing {
+                        Some(idx) => history.replace_round(idx, round).map(|_| ()),
+                        None => history.insert_round(round),
+                    })?;
+                    match_state.selected_round = None;
+                    match_state.reset_builder();
+                    if match_state.is_finished() {
+                        self.save_match()?
+                    }
+                }
+            }
+            UiEvent::MatchSelectRound(idx) => {
+                if let State::Match(ref mut match_state,_) = self {
+                    if match_state.selected_round == Some(idx) {
+                        match_state.selected_round = None;
+                    } else if idx < match_state.match_len() {
+                        match_state.selected_round = Some(idx);
+                    }
+                }
+            }
+            UiEvent::MatchEditRound => {
+                if let State::Match(ref mut match_state,_) = self {
+                    match_state.edit_round()
+                }
+            }
+            UiEvent::MatchCancelEdit => {
+                if let State::Match(ref mut match_state,_) = self {
+                    if match_state.editing.is_some() {
+                        match_state.reset_builder()
                     }
                 }
             }
+            UiEvent::MatchMoveRoundUp => {
+                if let State::Match(ref mut match_state,_) = self {
+                    let idx = match match_state.selected_round {
+                        Some(idx) if idx > 0 => idx,
+                        _ => return Ok(()),
+                    };
+                    match_state.change_rounds(|history| history.move_round(idx, idx - 1))?;
+                    match_state.selected_round = Some(idx - 1);
+                    match_state.reset_builder();
+                }
+            }
+            UiEvent::MatchDeleteRound => {
+                if let State::Match(ref mut match_state,_) = self {
+                    let idx = match match_state.selected_round {
+                        Some(idx) => idx,
+                        None => return Ok(()),
+                    };
+                    match_state.change_rounds(|history| history.remove_round(idx).map(|_| ()))?;
+                    match_state.selected_round = None;
+                    match_state.reset_builder();
+                }
+            }
+            UiEvent::RetrySaveMatch => {
+                if let State::Match(ref match_state, _) = self {
+                    if match_state.is_finished() {
+                        self.save_match()?
+                    }
+                }
+            }
+            UiEvent::Quit => {
+                *self = State::Exit;
+            }
+            // handled by `event`, there is nothing to undo outside roster and match
+            UiEvent::Undo | UiEvent::Redo => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use overwatch::overwatch_3v3::CompScorer;
+
+    fn match_screen() -> State {
+        let roster = Roster::new("player1", "player2", "player3");
+        let match_state = MatchState::new(roster, None, HistoryScorer::default());
+        State::Match(Box::new(match_state), MatchDb::default())
+    }
+
+    fn picked(state: &State) -> Vec<Option<Hero>> {
+        match state {
+            State::Match(match_state, _) => {
+                Player::iter().map(|p| match_state.get_hero(p)).collect()
+            },
+            s => panic!("expected the match screen: {:?}", s),
+        }
+    }
+
+    #[test]
+    fn undo_then_redo_in_order() {
+        let mut history = UndoHistory::default();
+        history.push(0);
+        history.push(1);
+
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!(history.undo(1), Some(0));
+        assert_eq!(history.undo(0), None);
+        assert_eq!(history.redo(0), Some(1));
+        assert_eq!(history.redo(1), Some(2));
+        assert_eq!(history.redo(2), None);
+    }
+
+    #[test]
+    fn new_edit_drops_redo() {
+        let mut history = UndoHistory::default();
+        history.push(0);
+        assert_eq!(history.undo(1), Some(0));
+
+        history.push(0);
+        assert_eq!(history.redo(2), None);
+        assert_eq!(history.undo(2), Some(0));
+    }
+
+    #[test]
+    fn oldest_step_evicted_at_limit() {
+        let mut history = UndoHistory::default();
+        for step in 0..=UNDO_LIMIT {
+            history.push(step);
+        }
+
+        let mut current = UNDO_LIMIT + 1;
+        let mut steps = 0;
+        while let Some(previous) = history.undo(current) {
+            current = previous;
+            steps += 1;
+        }
+        assert_eq!(steps, UNDO_LIMIT);
+        assert_eq!(current, 1);
+    }
+
+    #[test]
+    fn undo_and_redo_hero_picks() {
+        let mut state = match_screen();
+        state.event(UiEvent::RoundSelectHero(Hero::Ana)).unwrap();
+        state.event(UiEvent::RoundSelectHero(Hero::Mei)).unwrap();
+        // selecting the player again changes nothing, so it isn't an undo step
+        state.event(UiEvent::RoundSelectPlayer(Player::Three)).unwrap();
+
+        state.event(UiEvent::Undo).unwrap();
+        assert_eq!(picked(&state), vec![Some(Hero::Ana), None, None]);
+        state.event(UiEvent::Undo).unwrap();
+        assert_eq!(picked(&state), vec![None, None, None]);
+        state.event(UiEvent::Redo).unwrap();
+        state.event(UiEvent::Redo).unwrap();
+        assert_eq!(picked(&state), vec![Some(Hero::Ana), Some(Hero::Mei), None]);
+    }
+
+    /// Win a round with each comp, which finishes the match after three.
+    fn win_rounds(state: &mut State, comps: &[[Hero; 3]]) {
+        for comp in comps {
+            for hero in comp {
+                state.event(UiEvent::RoundSelectHero(*hero)).unwrap();
+            }
+            state.event(UiEvent::RoundToggleOutcome).unwrap();
+            state.event(UiEvent::RoundRecord).unwrap();
+        }
+    }
+
+    const WINNING_COMPS: [[Hero; 3]; 3] = [
+        [Hero::Ana, Hero::Mei, Hero::Sigma],
+        [Hero::Mercy, Hero::Pharah, Hero::Reinhardt],
+        [Hero::Lucio, Hero::Genji, Hero::Winston],
+    ];
+
+    #[test]
+    fn saved_match_leaves_no_undo_step() {
+        let mut state = match_screen();
+        win_rounds(&mut state, &WINNING_COMPS);
+
+        match &state {
+            State::RosterSelect(roster_state, db) => {
+                assert_eq!(db.match_count().unwrap(), 1);
+                assert!(roster_state.undo.undo.is_empty());
+            },
+            s => panic!("expected the roster screen: {:?}", s),
+        }
+    }
+
+    #[test]
+    fn roster_entered_by_alias_scores_with_player_history() {
+        let mut state = match_screen();
+        win_rounds(&mut state, &WINNING_COMPS);
+        if let State::RosterSelect(_, ref mut db) = state {
+            db.add_battletag_alias(&BattleTag::new("player1"), &BattleTag::new("smurf1"))
+                .unwrap();
+        }
+        for _ in 0..3 {
+            state.event(UiEvent::RemoveFromRoster(Player::One)).unwrap();
+        }
+        for battletag in &["smurf1", "player2", "player3"] {
+            state.event(UiEvent::RecordBattletag(battletag.to_string())).unwrap();
+            state.event(UiEvent::EnterBattleTag).unwrap();
+        }
+
+        match &state {
+            State::RosterSelect(roster_state, db) => {
+                let roster = roster_state.ow_roster().unwrap();
+                assert_eq!(roster, Roster::new("player1", "player2", "player3"));
+                let scorer = db.comp_scorer().unwrap();
+                let strangers = Roster::new("stranger1", "stranger2", "stranger3");
+                let comp = [Hero::Ana, Hero::Mei, Hero::Sigma];
+                assert!(scorer.score(&roster, comp) > scorer.score(&strangers, comp));
+            },
+            s => panic!("expected the roster screen: {:?}", s),
+        }
+    }
+
+    #[test]
+    fn edit_round_of_unsaved_match() {
+        let mut state = match_screen();
+        if let State::Match(ref mut match_state, _) = state {
+            // as a failed save leaves it
+            for comp in &WINNING_COMPS {
+                let mut builder = match_state.builder.next_round();
+                for player in Player::iter() {
+                    builder.set_player(player, comp[player.index()]);
+                }
+                builder.set_outcome(RoundOutcome::Win);
+                match_state.history.insert_round(builder.finalize().unwrap()).unwrap();
+            }
+            match_state.save_failed = true;
+        }
+
+        state.event(UiEvent::MatchSelectRound(0)).unwrap();
+        state.event(UiEvent::MatchEditRound).unwrap();
+        state.event(UiEvent::RoundSelectHero(Hero::Zenyatta)).unwrap();
+        state.event(UiEvent::RoundRecord).unwrap();
+
+        match &state {
+            State::RosterSelect(_, db) => {
+                let saved = db.load_match(1).unwrap().match_result;
+                assert_eq!(saved.get_round(0).unwrap().player1.1, Hero::Zenyatta);
+            },
+            s => panic!("expected the roster screen: {:?}", s),
         }
     }
 }
@@ -1,7 +1,5 @@
 #![allow(dead_code)]
 
-use glium;
-use std;
 
 pub struct GliumDisplayWinitWrapper(pub glium::Display);
 
@@ -4,86 +4,65 @@ use crate::state::{State, UiEvent};
 use crate::support;
 use glium::glutin;
 use glium::Surface;
-use overwatch::Hero;
-use rusttype;
+use std::any::Any;
+use std::error::Error;
+use std::panic::{self, AssertUnwindSafe};
 
 const WIDTH: u32 = 800;
 const HEIGHT: u32 = 600;
 
-pub fn load_font() -> rusttype::Font<'static> {
+pub fn load_font() -> Result<rusttype::Font<'static>, rusttype::Error> {
     //let font_data = include_bytes!("../../assets/fonts/NotoSans/NotoSans-Regular.ttf");
     let font_data = include_bytes!("../../assets/fonts/Overwatch/bignoodletoo.ttf");
     //let font_data = include_bytes!("../../assets/fonts/Overwatch/koverwatch.ttf");
-    let collection =
-        rusttype::FontCollection::from_bytes(font_data as &[u8]).expect("font was invalid?");
+    let collection = rusttype::FontCollection::from_bytes(font_data as &[u8])?;
 
-    collection
-        .into_font()
-        .expect("expected loading embedded font to succeed")
+    collection.into_font()
 }
 
-pub fn init_window() -> (glutin::EventsLoop, App) {
+pub fn init_window() -> Result<(glutin::EventsLoop, App), Box<dyn Error>> {
     // Create window.
     let events_loop = glutin::EventsLoop::new();
     let window = glutin::WindowBuilder::new()
         .with_title("Overwatch 3v3 Elimination - Team Manager")
         .with_dimensions((WIDTH, HEIGHT).into());
     let context = glutin::ContextBuilder::new().with_vsync(true);
-    let display =
-        glium::Display::new(window, context, &events_loop).expect("unable to create new window");
+    let display = glium::Display::new(window, context, &events_loop)?;
 
     let display = support::GliumDisplayWinitWrapper(display);
 
     // Create UI and other components.
-    let mut app = App::new(display, &events_loop);
+    let mut app = App::new(display, &events_loop)?;
 
     // Add font.
-    app.ui.fonts.insert(load_font());
+    app.ui.fonts.insert(load_font()?);
 
-    (events_loop, app)
+    Ok((events_loop, app))
 }
 
-pub fn main_window_loop(mut events: glutin::EventsLoop, mut app: App) {
+/// The message a panic was raised with, for the fatal error screen.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "an unknown error occurred".to_string()
+    }
+}
+
+/// Run the app until the window closes, first applying the `startup` events.
+pub fn main_window_loop(mut events: glutin::EventsLoop, mut app: App, startup: Vec<UiEvent>) {
     let mut event_loop = support::EventLoop::new();
 
     let mut state = State::new();
-    state.event(UiEvent::OpenDatabase("ow3v3.dat".into()));
-
-    // THIS BLOCK SKIPS Roster selection
-    if let State::RosterSelect(ref mut rs, _) = state {
-        rs.roster = vec!["player1".into(), "player2".into(), "player3".into()];
-    }
-    state.event(UiEvent::RosterPlay);
-    state.event(UiEvent::RoundSelectHero(Hero::Widowmaker));
-    state.event(UiEvent::RoundSelectHero(Hero::Moira));
-    state.event(UiEvent::RoundSelectHero(Hero::Mei));
-    state.event(UiEvent::RoundToggleOutcome);
-    state.event(UiEvent::RoundToggleOutcome);
-    state.event(UiEvent::RoundRecord);
-    state.event(UiEvent::RoundSelectHero(Hero::Pharah));
-    state.event(UiEvent::RoundSelectHero(Hero::Torbjorn));
-    state.event(UiEvent::RoundSelectHero(Hero::Mercy));
-    state.event(UiEvent::RoundToggleOutcome);
-    state.event(UiEvent::RoundRecord);
-    state.event(UiEvent::RoundSelectHero(Hero::Roadhog));
-    state.event(UiEvent::RoundSelectHero(Hero::Zarya));
-    state.event(UiEvent::RoundSelectHero(Hero::Zenyatta));
-    state.event(UiEvent::RoundToggleOutcome);
-    state.event(UiEvent::RoundRecord);
-    state.event(UiEvent::RoundSelectHero(Hero::Widowmaker));
-    state.event(UiEvent::RoundSelectHero(Hero::Moira));
-    state.event(UiEvent::RoundSelectHero(Hero::Mei));
-    // END THIS BLOCK SKIPS Roster selection
+    state.transform(startup.into_iter(), &mut app.notifications);
 
     debug!("Starting event loop.");
 
     'main: loop {
         for event in event_loop.next(&mut events) {
             trace!("top of main loop: {:?}", event);
-            if let State::Exit = state {
-                info!("exiting...");
-                break 'main;
-            }
 
             if let support::WindowEvent::Glutin(gevent) = event {
                 if let Some(gevent) = support::convert_event(gevent.clone(), &app.display) {
@@ -91,9 +70,9 @@ pub fn main_window_loop(mut events: glutin::EventsLoop, mut app: App) {
                     event_loop.needs_update();
                 }
 
-                match gevent {
-                    glium::glutin::Event::WindowEvent { event, .. } => match event {
-                        // Break from the loop upon `Escape`.
+                // Exit upon `Escape`.
+                if let glium::glutin::Event::WindowEvent {
+                    event:
                         glium::glutin::WindowEvent::CloseRequested
                         | glium::glutin::WindowEvent::KeyboardInput {
                             input:
@@ -102,19 +81,91 @@ pub fn main_window_loop(mut events: glutin::EventsLoop, mut app: App) {
                                     ..
                                 },
                             ..
-                        } => {
-                            state = State::Exit;
-                            break 'main;
                         },
-                        _ => (),
-                    },
-                    _ => (),
+                    ..
+                } = gevent
+                {
+                    state = State::Exit;
+                }
+
+                // Undo with `Ctrl+Z`, redo with `Ctrl+Shift+Z`.
+                if let glium::glutin::Event::WindowEvent {
+                    event:
+                        glium::glutin::WindowEvent::KeyboardInput {
+                            input:
+                                glium::glutin::KeyboardInput {
+                                    state: glium::glutin::ElementState::Pressed,
+                                    virtual_keycode: Some(glium::glutin::VirtualKeyCode::Z),
+                                    modifiers,
+                                    ..
+                                },
+                            ..
+                        },
+                    ..
+                } = gevent
+                {
+                    if modifiers.ctrl {
+                        let event = if modifiers.shift {
+                            UiEvent::Redo
+                        } else {
+                            UiEvent::Undo
+                        };
+                        state.transform(Some(event).into_iter(), &mut app.notifications);
+                        event_loop.needs_update();
+                    }
+                }
+
+                // Arrow keys move through the battletag suggestions.
+                if let glium::glutin::Event::WindowEvent {
+                    event:
+                        glium::glutin::WindowEvent::KeyboardInput {
+                            input:
+                                glium::glutin::KeyboardInput {
+                                    state: glium::glutin::ElementState::Pressed,
+                                    virtual_keycode: Some(key),
+                                    ..
+                                },
+                            ..
+                        },
+                    ..
+                } = gevent
+                {
+                    let highlight = match key {
+                        glium::glutin::VirtualKeyCode::Down => {
+                            Some(UiEvent::HighlightNextSuggestion)
+                        },
+                        glium::glutin::VirtualKeyCode::Up => {
+                            Some(UiEvent::HighlightPreviousSuggestion)
+                        },
+                        _ => None,
+                    };
+                    if let Some(event) = highlight {
+                        state.transform(Some(event).into_iter(), &mut app.notifications);
+                        event_loop.needs_update();
+                    }
                 }
             }
 
-            let needs_update = layout::create_ui(&mut app, &mut state);
-            if needs_update {
-                event_loop.needs_update();
+            if let State::Exit = state {
+                info!("exiting...");
+                break 'main;
+            }
+
+            // A panic while handling the UI leaves the state unknown, so instead of
+            // carrying on with it the app switches to a screen explaining what happened.
+            let ui_update =
+                panic::catch_unwind(AssertUnwindSafe(|| layout::create_ui(&mut app, &mut state)));
+            match ui_update {
+                Ok(true) => event_loop.needs_update(),
+                Ok(false) => {},
+                Err(_) if matches!(state, State::Fatal(_)) => {
+                    error!("the error screen failed, exiting");
+                    break 'main;
+                },
+                Err(payload) => {
+                    state = State::Fatal(panic_message(payload));
+                    event_loop.needs_update();
+                },
             }
 
             // Render the `Ui` and then display it on the screen.
@@ -122,10 +173,12 @@ pub fn main_window_loop(mut events: glutin::EventsLoop, mut app: App) {
                 app.renderer.fill(&app.display.0, primitives, &app.images);
                 let mut target = app.display.0.draw();
                 target.clear_color(0.0, 0.0, 0.0, 1.0);
-                app.renderer
-                    .draw(&app.display.0, &mut target, &app.images)
-                    .unwrap();
-                target.finish().unwrap();
+                if let Err(e) = app.renderer.draw(&app.display.0, &mut target, &app.images) {
+                    error!("could not draw the window: {}", e);
+                }
+                if let Err(e) = target.finish() {
+                    error!("could not show the window: {}", e);
+                }
             }
 
             trace!("bottom of main loop");
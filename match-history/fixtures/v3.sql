@@ -0,0 +1,46 @@
+PRAGMA user_version=3;
+PRAGMA foreign_keys=OFF;
+BEGIN TRANSACTION;
+CREATE TABLE battletags (
+             id integer primary key,
+             name text not null unique
+         );
+INSERT INTO battletags VALUES(1,'player1');
+INSERT INTO battletags VALUES(2,'player2');
+INSERT INTO battletags VALUES(3,'player3');
+CREATE TABLE matches (
+             id integer primary key,
+             timestamp datetime default CURRENT_TIMESTAMP
+         , rounds_to_win integer not null default 3, max_rounds integer not null default 5, hero_lock text not null default 'winners', tie_break text not null default 'draw');
+INSERT INTO matches VALUES(1,'2019-11-02 20:15:00',3,5,'winners','draw');
+CREATE TABLE plays (
+             id integer primary key,
+             round_id integer not null,
+             battletag_id integer not null,
+             hero TEXT not null,
+             FOREIGN KEY(round_id) REFERENCES rounds(id),
+             FOREIGN KEY(battletag_id) REFERENCES battletags(id)
+         );
+INSERT INTO plays VALUES(1,1,1,'ana');
+INSERT INTO plays VALUES(2,1,2,'zenyatta');
+INSERT INTO plays VALUES(3,1,3,'sigma');
+INSERT INTO plays VALUES(4,2,1,'roadhog');
+INSERT INTO plays VALUES(5,2,2,'brigitte');
+INSERT INTO plays VALUES(6,2,3,'mei');
+INSERT INTO plays VALUES(7,3,1,'pharah');
+INSERT INTO plays VALUES(8,3,2,'soldier-76');
+INSERT INTO plays VALUES(9,3,3,'mercy');
+INSERT INTO plays VALUES(10,4,1,'mccree');
+INSERT INTO plays VALUES(11,4,2,'reinhardt');
+INSERT INTO plays VALUES(12,4,3,'lucio');
+CREATE TABLE IF NOT EXISTS "rounds" (
+             id integer primary key,
+             match_id integer not null,
+             outcome text not null,
+             FOREIGN KEY(match_id) REFERENCES matches(id)
+         );
+INSERT INTO rounds VALUES(1,1,'loss');
+INSERT INTO rounds VALUES(2,1,'win');
+INSERT INTO rounds VALUES(3,1,'win');
+INSERT INTO rounds VALUES(4,1,'win');
+COMMIT;
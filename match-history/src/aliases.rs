@@ -0,0 +1,379 @@
+//! One player, several battletags.
+//!
+//! A player is the battletag row with no `canonical_id`; their aliases point at it. Plays
+//! and teams only ever refer to the player, so an alias is just another name to find them by.
+
+use crate::{battletag_display, join_canonical, MatchDb, MatchDbError};
+use crate::{SCHEMA_TABLE_BATTLETAGS, SCHEMA_TABLE_PLAYS, SCHEMA_TABLE_TEAMS};
+use overwatch::BattleTag;
+use rusqlite::types::Value;
+use rusqlite::Error as RusqliteError;
+use rusqlite::{Connection, ErrorCode, OptionalExtension};
+use std::ops::Deref;
+
+impl MatchDb {
+    /// The id of the player `battletag` belongs to, whether it is their battletag or an alias.
+    pub fn canonical_id(&self, battletag: &BattleTag) -> Result<Option<i64>, MatchDbError> {
+        canonical_id(&self.conn, battletag)
+    }
+
+    /// The battletag of the player `battletag` belongs to, as they spell it.
+    pub fn canonical_battletag(
+        &self,
+        battletag: &BattleTag,
+    ) -> Result<Option<BattleTag>, MatchDbError> {
+        let canonical = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM {} a {} WHERE a.name = ?1",
+                    battletag_display("b"),
+                    SCHEMA_TABLE_BATTLETAGS,
+                    join_canonical("a", "b")
+                ),
+                &[battletag.key()],
+                |r| r.get::<_, String>(0),
+            )
+            .optional()?;
+        Ok(canonical.map(BattleTag::new))
+    }
+
+    /// The other battletags a player is known by.
+    pub fn battletag_aliases(&self, battletag_id: i64) -> Result<Vec<BattleTag>, MatchDbError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM {} a WHERE a.canonical_id = ?1 ORDER BY a.id",
+            battletag_display("a"),
+            SCHEMA_TABLE_BATTLETAGS
+        ))?;
+        let aliases = stmt
+            .query_map([battletag_id], |r| r.get::<_, String>(0))?
+            .map(|r| r.map(BattleTag::new))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(aliases)
+    }
+
+    /// Let `alias` stand for the player `battletag` belongs to, returning the alias' id.
+    pub fn add_battletag_alias(
+        &mut self,
+        battletag: &BattleTag,
+        alias: &BattleTag,
+    ) -> Result<i64, MatchDbError> {
+        BattleTag::parse(alias.as_str()).map_err(MatchDbError::InvalidBattletag)?;
+        let tx = self.conn.transaction()?;
+        let player_id = canonical_id(tx.deref(), battletag)?
+            .ok_or_else(|| MatchDbError::BattletagDoesNotExist(battletag.clone()))?;
+        let alias_id = insert_alias(tx.deref(), alias, player_id)?;
+        tx.commit()?;
+        Ok(alias_id)
+    }
+
+    /// Give a player a new battletag, keeping the old one as an alias.
+    ///
+    /// Returns the player's id, which does not change. If `new` is taken by someone else
+    /// the two are different players as far as the history knows; merge them instead.
+    pub fn rename_battletag(
+        &mut self,
+        old: &BattleTag,
+        new: &BattleTag,
+    ) -> Result<i64, MatchDbError> {
+        BattleTag::parse(new.as_str()).map_err(MatchDbError::InvalidBattletag)?;
+        let tx = self.conn.transaction()?;
+        let player_id = canonical_id(tx.deref(), old)?
+            .ok_or_else(|| MatchDbError::BattletagDoesNotExist(old.clone()))?;
+        match canonical_id(tx.deref(), new)? {
+            // Going back to one of their own aliases
+            Some(id) if id == player_id => {
+                tx.execute(
+                    &format!(
+                        "DELETE FROM {} WHERE name = ?1 AND canonical_id = ?2",
+                        SCHEMA_TABLE_BATTLETAGS
+                    ),
+                    &[Value::Text(new.key().into()), Value::Integer(player_id)],
+                )?;
+            },
+            Some(_) => return Err(MatchDbError::BattletagAlreadyExists(new.clone())),
+            None => {},
+        }
+
+        let current = tx.query_row(
+            &format!(
+                "SELECT {} FROM {} b WHERE b.id = ?1",
+                battletag_display("b"),
+                SCHEMA_TABLE_BATTLETAGS
+            ),
+            [player_id],
+            |r| r.get::<_, String>(0),
+        )?;
+        let current = BattleTag::new(current);
+        tx.execute(
+            &format!(
+                "UPDATE {} SET name = ?1, display_name = ?2 WHERE id = ?3",
+                SCHEMA_TABLE_BATTLETAGS
+            ),
+            &[
+                Value::Text(new.key().into()),
+                Value::Text(new.as_str().into()),
+                Value::Integer(player_id),
+            ],
+        )?;
+        if current != *new {
+            insert_alias(tx.deref(), &current, player_id)?;
+        }
+        tx.commit()?;
+        Ok(player_id)
+    }
+
+    /// Fold the player `from_id` into `into_id`, for someone who was entered twice.
+    ///
+    /// Their plays and team places move over, and their battletag and aliases become aliases
+    /// of `into_id`. It all happens in one transaction.
+    pub fn merge_battletags(&mut self, from_id: i64, into_id: i64) -> Result<(), MatchDbError> {
+        let tx = self.conn.transaction()?;
+        let from_id = canonical_id_of(tx.deref(), from_id)?;
+        let into_id = canonical_id_of(tx.deref(), into_id)?;
+        if from_id == into_id {
+            return Ok(());
+        }
+        tx.execute(
+            &format!(
+                "UPDATE {} SET battletag_id = ?2 WHERE battletag_id = ?1",
+                SCHEMA_TABLE_PLAYS
+            ),
+            [from_id, into_id],
+        )?;
+        for column in &["player1_id", "player2_id", "player3_id"] {
+            tx.execute(
+                &format!(
+                    "UPDATE {0} SET {1} = ?2 WHERE {1} = ?1",
+                    SCHEMA_TABLE_TEAMS, column
+                ),
+                [from_id, into_id],
+            )?;
+        }
+        tx.execute(
+            &format!(
+                "UPDATE {} SET canonical_id = ?2 WHERE id = ?1 OR canonical_id = ?1",
+                SCHEMA_TABLE_BATTLETAGS
+            ),
+            [from_id, into_id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+fn canonical_id<C: Deref<Target = Connection>>(
+    conn: C,
+    battletag: &BattleTag,
+) -> Result<Option<i64>, MatchDbError> {
+    Ok(conn
+        .query_row(
+            &format!(
+                "SELECT coalesce(canonical_id, id) FROM {} WHERE name = ?1",
+                SCHEMA_TABLE_BATTLETAGS
+            ),
+            &[battletag.key()],
+            |r| r.get(0),
+        )
+        .optional()?)
+}
+
+fn canonical_id_of<C: Deref<Target = Connection>>(
+    conn: C,
+    battletag_id: i64,
+) -> Result<i64, MatchDbError> {
+    conn.query_row(
+        &format!(
+            "SELECT coalesce(canonical_id, id) FROM {} WHERE id = ?1",
+            SCHEMA_TABLE_BATTLETAGS
+        ),
+        [battletag_id],
+        |r| r.get(0),
+    )
+    .optional()?
+    .ok_or(MatchDbError::BattletagIdDoesNotExist(battletag_id))
+}
+
+/// Unlike `add_battletag_alias` this takes any name, as one being renamed away from may
+/// predate battletag validation.
+fn insert_alias<C: Deref<Target = Connection>>(
+    conn: C,
+    alias: &BattleTag,
+    player_id: i64,
+) -> Result<i64, MatchDbError> {
+    conn.execute(
+        &format!(
+            "INSERT INTO {} (name, display_name, canonical_id) VALUES (?1, ?2, ?3)",
+            SCHEMA_TABLE_BATTLETAGS
+        ),
+        &[
+            Value::Text(alias.key().into()),
+            Value::Text(alias.as_str().into()),
+            Value::Integer(player_id),
+        ],
+    )
+    .map_err(|e| {
+        if let RusqliteError::SqliteFailure(ffierr, _) = &e {
+            if let ErrorCode::ConstraintViolation = ffierr.code {
+                return MatchDbError::BattletagAlreadyExists(alias.clone());
+            }
+        }
+        e.into()
+    })?;
+    Ok(conn.last_insert_rowid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics::PlayerWinRate;
+    use crate::test_util::{record, round_of};
+    use overwatch::overwatch_3v3::{Roster, RoundOutcome};
+    use overwatch::Hero;
+
+    /// Record a round of `players` on Ana, Mei and Sigma.
+    fn record_players(db: &mut MatchDb, players: [&str; 3], outcome: RoundOutcome) -> i64 {
+        let heros = [Hero::Ana, Hero::Mei, Hero::Sigma];
+        record(db, None, &[round_of(players, heros, outcome)])
+    }
+
+    fn player_rounds(db: &MatchDb, battletag: &str) -> Option<u32> {
+        db.player_win_rates()
+            .unwrap()
+            .into_iter()
+            .find(|r: &PlayerWinRate| r.battletag.as_str() == battletag)
+            .map(|r| r.win_rate.rounds())
+    }
+
+    #[test]
+    fn aliases_resolve_to_their_player() {
+        let mut db = MatchDb::default();
+        let player_id = db.record_battletag(&BattleTag::new("Player1")).unwrap();
+        db.add_battletag_alias(&BattleTag::new("player1"), &BattleTag::new("Smurf1"))
+            .unwrap();
+        assert_eq!(
+            db.canonical_id(&BattleTag::new("smurf1")).unwrap(),
+            Some(player_id)
+        );
+        assert_eq!(db.canonical_id(&BattleTag::new("nobody")).unwrap(), None);
+        assert_eq!(
+            db.battletag_aliases(player_id).unwrap(),
+            vec![BattleTag::new("Smurf1")]
+        );
+
+        record_players(&mut db, ["smurf1", "player2", "player3"], RoundOutcome::Win);
+        assert_eq!(player_rounds(&db, "Player1"), Some(1));
+        assert_eq!(player_rounds(&db, "Smurf1"), None);
+        assert_eq!(
+            db.search_battletags("smurf").unwrap(),
+            vec![BattleTag::new("Player1")]
+        );
+        assert_eq!(
+            db.suggest_battletags("1", 5).unwrap(),
+            vec![BattleTag::new("Player1")]
+        );
+        assert_eq!(
+            db.canonical_battletag(&BattleTag::new("SMURF1")).unwrap(),
+            Some(BattleTag::new("Player1"))
+        );
+        assert_eq!(db.canonical_battletag(&BattleTag::new("nobody")).unwrap(), None);
+
+        match db.add_battletag_alias(&BattleTag::new("player2"), &BattleTag::new("smurf1")) {
+            Err(MatchDbError::BattletagAlreadyExists(_)) => {},
+            r => panic!("expected existing battletag error: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn rename_keeps_history() {
+        let mut db = MatchDb::default();
+        let match_id = record_players(
+            &mut db,
+            ["player1", "player2", "player3"],
+            RoundOutcome::Win,
+        );
+        let team = db
+            .create_team("regulars", &Roster::new("player1", "player2", "player3"))
+            .unwrap();
+
+        let player_id = db
+            .rename_battletag(&BattleTag::new("player1"), &BattleTag::new("Renamed1"))
+            .unwrap();
+        assert_eq!(
+            db.canonical_id(&BattleTag::new("player1")).unwrap(),
+            Some(player_id)
+        );
+        let round = db
+            .load_match(match_id)
+            .unwrap()
+            .match_result
+            .iter()
+            .next()
+            .cloned();
+        assert_eq!(round.unwrap().player1.0.as_str(), "Renamed1");
+        assert_eq!(db.get_team(team.id).unwrap().roster.0.as_str(), "Renamed1");
+        record_players(
+            &mut db,
+            ["player1", "player2", "player3"],
+            RoundOutcome::Loss,
+        );
+        assert_eq!(player_rounds(&db, "Renamed1"), Some(2));
+
+        match db.rename_battletag(&BattleTag::new("renamed1"), &BattleTag::new("player2")) {
+            Err(MatchDbError::BattletagAlreadyExists(_)) => {},
+            r => panic!("expected existing battletag error: {:?}", r),
+        }
+        db.rename_battletag(&BattleTag::new("renamed1"), &BattleTag::new("player1"))
+            .unwrap();
+        assert_eq!(
+            db.battletag_aliases(player_id).unwrap(),
+            vec![BattleTag::new("Renamed1")]
+        );
+    }
+
+    #[test]
+    fn merge_moves_plays_and_teams() {
+        let mut db = MatchDb::default();
+        record_players(
+            &mut db,
+            ["player1", "player2", "player3"],
+            RoundOutcome::Win,
+        );
+        record_players(
+            &mut db,
+            ["player1", "player2", "twice3"],
+            RoundOutcome::Loss,
+        );
+        let team = db
+            .create_team("regulars", &Roster::new("player1", "player2", "twice3"))
+            .unwrap();
+        db.add_battletag_alias(&BattleTag::new("twice3"), &BattleTag::new("thrice3"))
+            .unwrap();
+        let from = db.canonical_id(&BattleTag::new("twice3")).unwrap().unwrap();
+        let into = db
+            .canonical_id(&BattleTag::new("player3"))
+            .unwrap()
+            .unwrap();
+
+        db.merge_battletags(from, into).unwrap();
+        assert_eq!(player_rounds(&db, "player3"), Some(2));
+        assert_eq!(player_rounds(&db, "twice3"), None);
+        assert_eq!(
+            db.canonical_id(&BattleTag::new("thrice3")).unwrap(),
+            Some(into)
+        );
+        assert_eq!(
+            db.get_team(team.id).unwrap().roster.2,
+            BattleTag::new("player3")
+        );
+        assert_eq!(db.battletag_aliases(into).unwrap().len(), 2);
+
+        // Merging through an alias, or into yourself, changes nothing
+        db.merge_battletags(from, into).unwrap();
+        assert_eq!(player_rounds(&db, "player3"), Some(2));
+        match db.merge_battletags(from, 99) {
+            Err(MatchDbError::BattletagIdDoesNotExist(99)) => {},
+            r => panic!("expected missing battletag error: {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,654 @@
+//! Round win rates over the stored match history.
+//!
+//! Rows are aggregated in Rust rather than SQL so that every hero name goes through
+//! `Hero::from_str`, which folds aliases and renamed heroes into a single entry.
+
+use crate::{battletag_display, build_match, game_rules, join_canonical};
+use crate::{MatchDb, MatchDbError, SCHEMA_TABLE_BATTLETAGS};
+use crate::{SCHEMA_TABLE_MATCH, SCHEMA_TABLE_PLAYS, SCHEMA_TABLE_ROUND, SCHEMA_TABLE_TEAMS};
+use overwatch::overwatch_3v3::{Match, Round, RoundOutcome};
+use overwatch::{BattleTag, Hero, Map, Role};
+use rusqlite::NO_PARAMS;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::str::FromStr;
+
+/// z-score for a 95% confidence interval.
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// Round results for some slice of the match history.
+///
+/// Draws count towards the sample size but not as wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WinRate {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl WinRate {
+    pub fn rounds(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    pub fn rate(&self) -> f64 {
+        if self.rounds() == 0 {
+            return 0.0;
+        }
+        f64::from(self.wins) / f64::from(self.rounds())
+    }
+
+    /// The Wilson score interval for the true win rate, at 95% confidence.
+    ///
+    /// Unlike the normal approximation this stays inside `[0, 1]` and is still
+    /// meaningful for the handful of rounds most heroes have.
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        let n = f64::from(self.rounds());
+        if n == 0.0 {
+            return (0.0, 1.0);
+        }
+        let p = self.rate();
+        let z2 = CONFIDENCE_Z * CONFIDENCE_Z;
+        let denom = 1.0 + z2 / n;
+        let center = (p + z2 / (2.0 * n)) / denom;
+        let margin = CONFIDENCE_Z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denom;
+        ((center - margin).max(0.0), (center + margin).min(1.0))
+    }
+
+    fn record(&mut self, outcome: RoundOutcome) {
+        match outcome {
+            RoundOutcome::Win => self.wins += 1,
+            RoundOutcome::Loss => self.losses += 1,
+            RoundOutcome::Draw => self.draws += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeroWinRate {
+    pub hero: Hero,
+    pub win_rate: WinRate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoleWinRate {
+    pub role: Role,
+    pub win_rate: WinRate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerWinRate {
+    pub battletag: BattleTag,
+    pub win_rate: WinRate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerHeroWinRate {
+    pub battletag: BattleTag,
+    pub hero: Hero,
+    pub win_rate: WinRate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapWinRate {
+    pub map: Map,
+    pub win_rate: WinRate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeamWinRate {
+    pub team_id: i64,
+    pub name: String,
+    pub win_rate: WinRate,
+}
+
+/// How many heroes of a comp to consider together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompSize {
+    Duo,
+    Trio,
+}
+
+impl CompSize {
+    pub fn heros(self) -> usize {
+        match self {
+            CompSize::Duo => 2,
+            CompSize::Trio => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompWinRate {
+    /// Sorted, so the same heroes on different players are one comp.
+    pub heros: Vec<Hero>,
+    pub win_rate: WinRate,
+    /// The whole confidence interval lies above the baseline win rate.
+    pub significant: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynergyReport {
+    /// Results over every round, to compare comps against.
+    pub baseline: WinRate,
+    /// Best first, ranked by the lower bound of their confidence interval.
+    pub comps: Vec<CompWinRate>,
+}
+
+/// A single hero played by one of our players in a round.
+struct Play {
+    battletag: BattleTag,
+    hero: Hero,
+    /// What the hero's role was when the match was played.
+    role: Role,
+    outcome: RoundOutcome,
+}
+
+impl MatchDb {
+    /// Win rates of every hero we have played, most played first.
+    pub fn hero_win_rates(&self) -> Result<Vec<HeroWinRate>, MatchDbError> {
+        let plays = self.load_plays()?;
+        Ok(tally(&plays, |p| p.hero)
+            .into_iter()
+            .map(|(hero, win_rate)| HeroWinRate { hero, win_rate })
+            .collect())
+    }
+
+    /// Win rates of each role, most played first.
+    ///
+    /// Heroes count towards the role they had at the time, so a Doomfist round from before
+    /// he became a tank is still a dps round.
+    pub fn role_win_rates(&self) -> Result<Vec<RoleWinRate>, MatchDbError> {
+        let plays = self.load_plays()?;
+        Ok(tally(&plays, |p| p.role)
+            .into_iter()
+            .map(|(role, win_rate)| RoleWinRate { role, win_rate })
+            .collect())
+    }
+
+    /// Win rates of every battletag, most played first.
+    pub fn player_win_rates(&self) -> Result<Vec<PlayerWinRate>, MatchDbError> {
+        let plays = self.load_plays()?;
+        Ok(tally(&plays, |p| p.battletag.clone())
+            .into_iter()
+            .map(|(battletag, win_rate)| PlayerWinRate {
+                battletag,
+                win_rate,
+            })
+            .collect())
+    }
+
+    /// Win rates of every hero each battletag has played, most played first.
+    pub fn player_hero_win_rates(&self) -> Result<Vec<PlayerHeroWinRate>, MatchDbError> {
+        let plays = self.load_plays()?;
+        Ok(tally(&plays, |p| (p.battletag.clone(), p.hero))
+            .into_iter()
+            .map(|((battletag, hero), win_rate)| PlayerHeroWinRate {
+                battletag,
+                hero,
+                win_rate,
+            })
+            .collect())
+    }
+
+    /// Win rates on every map, for matches where the map was recorded, most played first.
+    pub fn map_win_rates(&self) -> Result<Vec<MapWinRate>, MatchDbError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "\
+             SELECT m.map, r.outcome FROM {} m \
+             JOIN {} r ON r.match_id = m.id \
+             WHERE m.map IS NOT NULL",
+            SCHEMA_TABLE_MATCH, SCHEMA_TABLE_ROUND
+        ))?;
+        let rows = stmt
+            .query_map(NO_PARAMS, |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<Result<Vec<(String, String)>, _>>()?;
+        let rounds = rows
+            .into_iter()
+            .map(|(map, outcome)| {
+                Ok((
+                    Map::from_str(&map).map_err(MatchDbError::UnknownMap)?,
+                    RoundOutcome::from_str(&outcome).map_err(MatchDbError::UnknownOutcome)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, MatchDbError>>()?;
+        Ok(tally_outcomes(rounds)
+            .into_iter()
+            .map(|(map, win_rate)| MapWinRate { map, win_rate })
+            .collect())
+    }
+
+    /// Win rates of every saved team, for matches recorded with one, most played first.
+    pub fn team_win_rates(&self) -> Result<Vec<TeamWinRate>, MatchDbError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "\
+             SELECT t.id, t.name, r.outcome FROM {} t \
+             JOIN {} m ON m.team_id = t.id \
+             JOIN {} r ON r.match_id = m.id",
+            SCHEMA_TABLE_TEAMS, SCHEMA_TABLE_MATCH, SCHEMA_TABLE_ROUND
+        ))?;
+        let rows = stmt
+            .query_map(NO_PARAMS, |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+            .collect::<Result<Vec<(i64, String, String)>, _>>()?;
+        let rounds = rows
+            .into_iter()
+            .map(|(id, name, outcome)| {
+                Ok((
+                    (name, id),
+                    RoundOutcome::from_str(&outcome).map_err(MatchDbError::UnknownOutcome)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, MatchDbError>>()?;
+        Ok(tally_outcomes(rounds)
+            .into_iter()
+            .map(|((name, team_id), win_rate)| TeamWinRate {
+                team_id,
+                name,
+                win_rate,
+            })
+            .collect())
+    }
+
+    /// Win rates of every duo or trio of heroes we have played together.
+    ///
+    /// Comps with fewer than `min_rounds` rounds are left out. Ranking by the lower
+    /// confidence bound keeps a lucky comp with two rounds from topping the list.
+    pub fn comp_win_rates(
+        &self,
+        size: CompSize,
+        min_rounds: u32,
+    ) -> Result<SynergyReport, MatchDbError> {
+        let rounds = self.load_all_rounds()?;
+        let mut baseline = WinRate::default();
+        for r in &rounds {
+            baseline.record(r.outcome);
+        }
+
+        let mut comps = tally_outcomes(rounds.iter().flat_map(|r| {
+            let mut heros = r.iter().collect::<Vec<_>>();
+            heros.sort();
+            combinations(&heros, size.heros())
+                .into_iter()
+                .map(move |comp| (comp, r.outcome))
+        }))
+        .into_iter()
+        .filter(|(_, win_rate)| win_rate.rounds() >= min_rounds)
+        .map(|(heros, win_rate)| CompWinRate {
+            heros,
+            win_rate,
+            significant: win_rate.confidence_interval().0 > baseline.rate(),
+        })
+        .collect::<Vec<_>>();
+        comps.sort_by(|a, b| {
+            let (a_low, _) = a.win_rate.confidence_interval();
+            let (b_low, _) = b.win_rate.confidence_interval();
+            b_low
+                .partial_cmp(&a_low)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| b.win_rate.rounds().cmp(&a.win_rate.rounds()))
+                .then_with(|| a.heros.cmp(&b.heros))
+        });
+        Ok(SynergyReport { baseline, comps })
+    }
+
+    /// Every round of every stored match, without enemy comps.
+    fn load_all_rounds(&self) -> Result<Vec<Round>, MatchDbError> {
+        let matches = self.load_all_matches()?;
+        Ok(matches.iter().flat_map(|(_, m)| m.iter().cloned()).collect())
+    }
+
+    /// Every play of every stored match, with the hero's role as it was then.
+    fn load_plays(&self) -> Result<Vec<Play>, MatchDbError> {
+        let mut plays = vec![];
+        for (timestamp, m) in self.load_all_matches()? {
+            for round in m.iter() {
+                for (battletag, hero) in &[&round.player1, &round.player2, &round.player3] {
+                    plays.push(Play {
+                        battletag: battletag.clone(),
+                        hero: *hero,
+                        role: hero.role_at(&timestamp),
+                        outcome: round.outcome,
+                    });
+                }
+            }
+        }
+        Ok(plays)
+    }
+
+    /// Every stored match with its timestamp, without enemy comps.
+    ///
+    /// A match that fails to load, such as one recorded before its rules were enforced,
+    /// is left out with a warning so it can't take the whole report down with it.
+    fn load_all_matches(&self) -> Result<Vec<(String, Match)>, MatchDbError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "\
+             SELECT id, timestamp, rounds_to_win, max_rounds, hero_lock, tie_break FROM {} \
+             ORDER BY id",
+            SCHEMA_TABLE_MATCH
+        ))?;
+        let matches = stmt
+            .query_map(NO_PARAMS, |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?))
+            })?
+            .collect::<Result<Vec<(i64, String, i64, i64, String, String)>, _>>()?;
+
+        let mut stmt = self.conn.prepare(&format!(
+            "\
+             SELECT r.match_id, r.id, r.outcome, {}, p.hero FROM {} p \
+             JOIN {} r ON r.id = p.round_id \
+             JOIN {} a ON a.id = p.battletag_id {} \
+             ORDER BY r.match_id, r.id, p.id",
+            battletag_display("b"),
+            SCHEMA_TABLE_PLAYS,
+            SCHEMA_TABLE_ROUND,
+            SCHEMA_TABLE_BATTLETAGS,
+            join_canonical("a", "b")
+        ))?;
+        let mut plays = HashMap::<i64, Vec<_>>::new();
+        for row in stmt.query_map(NO_PARAMS, |r| {
+            Ok((r.get(0)?, (r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))
+        })? {
+            let (match_id, play): (i64, (i64, String, String, String)) = row?;
+            plays.entry(match_id).or_default().push(play);
+        }
+
+        let mut loaded = vec![];
+        for (match_id, timestamp, rounds_to_win, max_rounds, hero_lock, tie_break) in matches {
+            let plays = plays.remove(&match_id).unwrap_or_default();
+            match game_rules(rounds_to_win, max_rounds, &hero_lock, &tie_break)
+                .and_then(|rules| build_match(match_id, rules, plays, HashMap::new()))
+            {
+                Ok(m) => loaded.push((timestamp, m)),
+                Err(e) => warn!("leaving match {} out of the analysis: {}", match_id, e),
+            }
+        }
+        Ok(loaded)
+    }
+}
+
+fn tally<K: Hash + Ord>(plays: &[Play], key: impl Fn(&Play) -> K) -> Vec<(K, WinRate)> {
+    tally_outcomes(plays.iter().map(|p| (key(p), p.outcome)))
+}
+
+/// Sum up outcomes per key, ordered by sample size and then by key.
+fn tally_outcomes<K: Hash + Ord>(
+    outcomes: impl IntoIterator<Item = (K, RoundOutcome)>,
+) -> Vec<(K, WinRate)> {
+    let mut rates = HashMap::<K, WinRate>::new();
+    for (key, outcome) in outcomes {
+        rates.entry(key).or_default().record(outcome);
+    }
+    let mut rates = rates.into_iter().collect::<Vec<_>>();
+    rates.sort_by(|(ka, a), (kb, b)| b.rounds().cmp(&a.rounds()).then_with(|| ka.cmp(kb)));
+    rates
+}
+
+/// Every `size` element subset of `heros`, keeping their order.
+fn combinations(heros: &[Hero], size: usize) -> Vec<Vec<Hero>> {
+    if size == 0 {
+        return vec![vec![]];
+    }
+    if heros.len() < size {
+        return vec![];
+    }
+    let (first, rest) = (heros[0], &heros[1..]);
+    let mut with_first = combinations(rest, size - 1);
+    for comp in &mut with_first {
+        comp.insert(0, first);
+    }
+    with_first.extend(combinations(rest, size));
+    with_first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{record, round};
+    use overwatch::overwatch_3v3::{GameRules, Roster};
+
+    fn test_db() -> MatchDb {
+        let mut db = MatchDb::default();
+        record(
+            &mut db,
+            Some(Map::Petra),
+            &[
+                round([Hero::Ana, Hero::Mei, Hero::Sigma], RoundOutcome::Loss),
+                round([Hero::Ana, Hero::Mei, Hero::Sigma], RoundOutcome::Win),
+                round(
+                    [Hero::Mercy, Hero::Ana, Hero::Reinhardt],
+                    RoundOutcome::Draw,
+                ),
+            ],
+        );
+        record(
+            &mut db,
+            None,
+            &[round(
+                [Hero::Ana, Hero::Lucio, Hero::Sigma],
+                RoundOutcome::Win,
+            )],
+        );
+        db
+    }
+
+    fn win_rate(wins: u32, losses: u32, draws: u32) -> WinRate {
+        WinRate {
+            wins,
+            losses,
+            draws,
+        }
+    }
+
+    #[test]
+    fn wilson_interval() {
+        let (low, high) = win_rate(8, 2, 0).confidence_interval();
+        assert!((low - 0.4902).abs() < 1e-4, "low: {}", low);
+        assert!((high - 0.9433).abs() < 1e-4, "high: {}", high);
+
+        assert_eq!(win_rate(0, 0, 0).confidence_interval(), (0.0, 1.0));
+        let (low, high) = win_rate(3, 0, 0).confidence_interval();
+        assert!(low > 0.0 && high == 1.0);
+    }
+
+    #[test]
+    fn hero_win_rates() {
+        let rates = test_db().hero_win_rates().unwrap();
+        assert_eq!(
+            rates[0],
+            HeroWinRate {
+                hero: Hero::Ana,
+                win_rate: win_rate(2, 1, 1),
+            }
+        );
+        assert_eq!(
+            rates[1],
+            HeroWinRate {
+                hero: Hero::Sigma,
+                win_rate: win_rate(2, 1, 0),
+            }
+        );
+        assert_eq!(rates.iter().map(|r| r.win_rate.rounds()).sum::<u32>(), 12);
+    }
+
+    #[test]
+    fn player_win_rates() {
+        let rates = test_db().player_win_rates().unwrap();
+        assert_eq!(rates.len(), 3);
+        for rate in &rates {
+            assert_eq!(rate.win_rate, win_rate(2, 1, 1));
+        }
+        assert_eq!(rates[0].battletag, BattleTag::new("player1"));
+    }
+
+    #[test]
+    fn player_hero_win_rates() {
+        let rates = test_db().player_hero_win_rates().unwrap();
+        assert_eq!(
+            rates[0],
+            PlayerHeroWinRate {
+                battletag: BattleTag::new("player1"),
+                hero: Hero::Ana,
+                win_rate: win_rate(2, 1, 0),
+            }
+        );
+        let player2_ana = rates
+            .iter()
+            .find(|r| r.battletag.as_str() == "player2" && r.hero == Hero::Ana)
+            .unwrap();
+        assert_eq!(player2_ana.win_rate, win_rate(0, 0, 1));
+    }
+
+    #[test]
+    fn map_win_rates() {
+        let rates = test_db().map_win_rates().unwrap();
+        assert_eq!(
+            rates,
+            vec![MapWinRate {
+                map: Map::Petra,
+                win_rate: win_rate(1, 1, 1),
+            }]
+        );
+    }
+
+    #[test]
+    fn role_win_rates_use_the_role_at_the_time() {
+        let mut db = MatchDb::default();
+        let comp = [Hero::Doomfist, Hero::Ana, Hero::Sigma];
+        record(&mut db, None, &[round(comp, RoundOutcome::Win)]);
+        db.conn
+            .execute(
+                &format!(
+                    "UPDATE {} SET timestamp = '2019-11-02 20:15:00'",
+                    SCHEMA_TABLE_MATCH
+                ),
+                NO_PARAMS,
+            )
+            .unwrap();
+        record(&mut db, None, &[round(comp, RoundOutcome::Loss)]);
+
+        assert_eq!(
+            db.role_win_rates().unwrap(),
+            vec![
+                RoleWinRate {
+                    role: Role::Tank,
+                    win_rate: win_rate(1, 2, 0),
+                },
+                RoleWinRate {
+                    role: Role::Support,
+                    win_rate: win_rate(1, 1, 0),
+                },
+                RoleWinRate {
+                    role: Role::Dps,
+                    win_rate: win_rate(1, 0, 0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn team_win_rates() {
+        let mut db = test_db();
+        let roster = Roster::new("player1", "player2", "player3");
+        let team = db.create_team("regulars", &roster).unwrap();
+        let mut m = Match::new(GameRules::elimination());
+        m.insert_round(round([Hero::Ana, Hero::Mei, Hero::Sigma], RoundOutcome::Win))
+            .unwrap();
+        m.insert_round(round([Hero::Ana, Hero::Mei, Hero::Sigma], RoundOutcome::Loss))
+            .unwrap();
+        db.record_team_match(&m, Some(team.id)).unwrap();
+        assert_eq!(
+            db.team_win_rates().unwrap(),
+            vec![TeamWinRate {
+                team_id: team.id,
+                name: "regulars".to_string(),
+                win_rate: win_rate(1, 1, 0),
+            }]
+        );
+    }
+
+    fn synergy_db() -> MatchDb {
+        let mut db = MatchDb::default();
+        for _ in 0..6 {
+            let r = round([Hero::Sigma, Hero::Mei, Hero::Ana], RoundOutcome::Win);
+            record(&mut db, None, &[r]);
+            let r = round(
+                [Hero::Mercy, Hero::Pharah, Hero::Reinhardt],
+                RoundOutcome::Loss,
+            );
+            record(&mut db, None, &[r]);
+        }
+        let r = round([Hero::Ana, Hero::Pharah, Hero::Lucio], RoundOutcome::Loss);
+        record(&mut db, None, &[r]);
+        db
+    }
+
+    #[test]
+    fn trio_synergy() {
+        let report = synergy_db().comp_win_rates(CompSize::Trio, 2).unwrap();
+        assert_eq!(report.baseline, win_rate(6, 7, 0));
+        assert_eq!(
+            report.comps,
+            vec![
+                CompWinRate {
+                    heros: vec![Hero::Ana, Hero::Mei, Hero::Sigma],
+                    win_rate: win_rate(6, 0, 0),
+                    significant: true,
+                },
+                CompWinRate {
+                    heros: vec![Hero::Mercy, Hero::Pharah, Hero::Reinhardt],
+                    win_rate: win_rate(0, 6, 0),
+                    significant: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn duo_synergy() {
+        let report = synergy_db().comp_win_rates(CompSize::Duo, 1).unwrap();
+        assert_eq!(report.comps.len(), 9);
+        assert_eq!(report.comps[0].heros, vec![Hero::Ana, Hero::Mei]);
+        assert!(report.comps[..3].iter().all(|c| c.significant));
+        assert!(report.comps[3..].iter().all(|c| !c.significant));
+
+        let filtered = synergy_db().comp_win_rates(CompSize::Duo, 2).unwrap();
+        assert_eq!(filtered.comps.len(), 6);
+    }
+
+    #[test]
+    fn synergy_skips_invalid_matches() {
+        let db = synergy_db();
+        // The last match's Lucio becomes a second Ana, which doesn't validate.
+        db.conn
+            .execute(
+                "UPDATE plays SET hero = 'ana' \
+                 WHERE round_id = (SELECT max(id) FROM rounds) AND hero = 'lucio'",
+                NO_PARAMS,
+            )
+            .unwrap();
+
+        let report = db.comp_win_rates(CompSize::Trio, 1).unwrap();
+        assert_eq!(report.baseline, win_rate(6, 6, 0));
+        assert_eq!(report.comps.len(), 2);
+        // the other reports leave out the same match
+        let ana = db.hero_win_rates().unwrap().into_iter().find(|r| r.hero == Hero::Ana);
+        assert_eq!(ana.unwrap().win_rate, win_rate(6, 0, 0));
+        let player1 = &db.player_win_rates().unwrap()[0];
+        assert_eq!(player1.win_rate, win_rate(6, 6, 0));
+    }
+
+    #[test]
+    fn hero_combinations() {
+        let heros = [Hero::Ana, Hero::Mei, Hero::Sigma];
+        assert_eq!(
+            combinations(&heros, 2),
+            vec![
+                vec![Hero::Ana, Hero::Mei],
+                vec![Hero::Ana, Hero::Sigma],
+                vec![Hero::Mei, Hero::Sigma],
+            ]
+        );
+        assert_eq!(combinations(&heros, 3), vec![heros.to_vec()]);
+    }
+}
@@ -1,8 +1,10 @@
+// `failure_derive` emits its impls inside an anonymous const
+#![allow(non_local_definitions)]
+
 #[macro_use]
 extern crate log;
 
-use overwatch;
-use overwatch::{BattleTag, Hero};
+use overwatch::{BattleTag, Hero, ParseBattleTagError, ParseHeroError, ParseMapError};
 
 use rusqlite::types::Value;
 use rusqlite::Error as RusqliteError;
@@ -12,13 +14,48 @@ use rusqlite::{Connection, OptionalExtension};
 
 use failure::Fail;
 use failure::_core::ops::Deref;
-use overwatch::overwatch_3v3::{CompBuilder, Match, Player, Roster, Round};
+use std::collections::HashMap;
+use overwatch::overwatch_3v3::{
+    CompBuilder, GameRules, Match, MatchHistoryError, ParseOutcomeError, ParseRuleError, Player,
+    Roster, Round, RoundOutcome,
+};
 use std::path;
+use std::str::FromStr;
 
 const SCHEMA_TABLE_BATTLETAGS: &str = "battletags";
 const SCHEMA_TABLE_MATCH: &str = "matches";
 const SCHEMA_TABLE_ROUND: &str = "rounds";
 const SCHEMA_TABLE_PLAYS: &str = "plays";
+const SCHEMA_TABLE_ENEMY_PLAYS: &str = "enemy_plays";
+const SCHEMA_TABLE_TEAMS: &str = "teams";
+
+mod aliases;
+pub mod analytics;
+mod migrations;
+mod scorer;
+mod teams;
+#[cfg(test)]
+mod test_util;
+
+pub use migrations::SCHEMA_VERSION;
+pub use scorer::HistoryScorer;
+pub use teams::Team;
+
+/// How a battletag is shown, for the battletags table aliased as `alias`.
+///
+/// The display name is null where the casing was never known, the lowercase name is
+/// the best there is then.
+fn battletag_display(alias: &str) -> String {
+    format!("coalesce({0}.display_name, {0}.name)", alias)
+}
+
+/// Join the player that the battletag aliased as `alias` resolves to, aliased as `canonical`.
+fn join_canonical(alias: &str, canonical: &str) -> String {
+    format!(
+        "JOIN {} {2} ON {2}.id = coalesce({1}.canonical_id, {1}.id)",
+        SCHEMA_TABLE_BATTLETAGS, alias, canonical
+    )
+}
 
 #[derive(Debug, Fail)]
 pub enum MatchDbError {
@@ -26,7 +63,40 @@ pub enum MatchDbError {
     BattletagAlreadyExists(BattleTag),
     #[fail(display = "Battletag '{}' does not exist", _0)]
     BattletagDoesNotExist(BattleTag),
-    #[fail(display = "Sqlite Error")]
+    #[fail(display = "Battletag {} does not exist", _0)]
+    BattletagIdDoesNotExist(i64),
+    #[fail(display = "Invalid battletag: {}", _0)]
+    InvalidBattletag(#[cause] ParseBattleTagError),
+    #[fail(display = "Team '{}' already exists", _0)]
+    TeamAlreadyExists(String),
+    #[fail(display = "Team {} does not exist", _0)]
+    TeamDoesNotExist(i64),
+    #[fail(display = "Match {} does not exist", _0)]
+    MatchDoesNotExist(i64),
+    #[fail(display = "Round {} does not have a play for every player", _0)]
+    IncompleteRound(i64),
+    #[fail(display = "Round {} has an incomplete enemy composition", _0)]
+    IncompleteEnemyComp(i64),
+    #[fail(display = "Unknown hero in database")]
+    UnknownHero(#[cause] ParseHeroError),
+    #[fail(display = "Unknown round outcome in database")]
+    UnknownOutcome(#[cause] ParseOutcomeError),
+    #[fail(display = "Unknown map in database")]
+    UnknownMap(#[cause] ParseMapError),
+    #[fail(display = "Unknown game rule in database")]
+    UnknownRule(#[cause] ParseRuleError),
+    #[fail(display = "Match {} is not valid: {}", _0, _1)]
+    InvalidMatch(i64, #[cause] MatchHistoryError),
+    #[fail(
+        display = "Database schema version {} is newer than this program supports ({})",
+        _0, _1
+    )]
+    SchemaTooNew(u32, u32),
+    #[fail(display = "No match database at '{}'", _0)]
+    DatabaseNotFound(String),
+    #[fail(display = "A file already exists at '{}'", _0)]
+    DatabaseAlreadyExists(String),
+    #[fail(display = "Sqlite Error: {}", _0)]
     SqliteError(#[cause] rusqlite::Error),
 }
 
@@ -41,56 +111,68 @@ pub struct MatchDb {
     conn: Connection,
 }
 
+/// A match as it was stored in the database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchRecord {
+    pub id: i64,
+    pub timestamp: String,
+    /// The saved team that played, if it was picked from one.
+    pub team_id: Option<i64>,
+    pub match_result: Match,
+}
+
+/// How many stored plays `MatchDb::update_hero_ids` moved off a former hero id.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HeroIdUpdates {
+    pub plays: usize,
+    pub enemy_plays: usize,
+}
+
+/// Open the match database at `path`, creating it if there is nothing there yet.
 pub fn open<P: AsRef<path::Path>>(path: P) -> Result<MatchDb, MatchDbError> {
-    let conn = Connection::open(path)?;
-    create_schema(&conn)?;
+    let mut conn = Connection::open(path)?;
+    migrations::migrate(&mut conn)?;
     Ok(MatchDb::new(conn))
 }
 
+/// Open a match database that must already exist, so a mistyped path is an error.
+pub fn open_existing<P: AsRef<path::Path>>(path: P) -> Result<MatchDb, MatchDbError> {
+    let path = path.as_ref();
+    if !path.is_file() {
+        return Err(MatchDbError::DatabaseNotFound(path.display().to_string()));
+    }
+    open(path)
+}
+
+/// Create a new, empty match database, refusing to touch an existing file.
+pub fn create<P: AsRef<path::Path>>(path: P) -> Result<MatchDb, MatchDbError> {
+    let path = path.as_ref();
+    if path.exists() {
+        return Err(MatchDbError::DatabaseAlreadyExists(path.display().to_string()));
+    }
+    open(path)
+}
+
 impl MatchDb {
     pub fn new(conn: Connection) -> MatchDb {
         MatchDb { conn }
     }
 
     pub fn get_or_insert_battletag_id(&self, battletag: &BattleTag) -> Result<i64, MatchDbError> {
-        let mut stmt = self.conn.prepare(&format!(
-            "SELECT id from {} WHERE name = (?1)",
-            SCHEMA_TABLE_BATTLETAGS
-        ))?;
-
-        let existing_id = stmt
-            .query_row(&[format!("{}", battletag)], |r| {
-                let s: rusqlite::Result<i64> = r.get(0);
-                s
-            })
-            .optional()?;
-        Ok(if let Some(id) = existing_id {
-            id
-        } else {
-            self.record_battletag(battletag)?
-        })
+        get_or_insert_battletag_id(&self.conn, battletag)
     }
 
     pub fn record_battletag(&self, battletag: &BattleTag) -> Result<i64, MatchDbError> {
-        let sql = format!("INSERT INTO {} (name) values (?1)", SCHEMA_TABLE_BATTLETAGS);
-        self.conn
-            .execute(&sql, &[battletag.as_str()])
-            .map_err(|e| {
-                let mut new_e = None;
-                if let RusqliteError::SqliteFailure(ffierr, _) = e {
-                    if let ErrorCode::ConstraintViolation = ffierr.code {
-                        new_e = Some(MatchDbError::BattletagAlreadyExists(battletag.clone()))
-                    }
-                }
-                new_e.unwrap_or_else(|| e.into())
-            })?;
-        Ok(self.conn.last_insert_rowid())
+        record_battletag(&self.conn, battletag)
     }
 
+    /// Players with a battletag or alias containing `search`.
     pub fn search_battletags(&self, search: &str) -> Result<Vec<BattleTag>, MatchDbError> {
         let mut stmt = self.conn.prepare(&format!(
-            "SELECT name from {} WHERE name LIKE (?1)",
-            SCHEMA_TABLE_BATTLETAGS
+            "SELECT {} from {} a {} WHERE a.name LIKE (?1) GROUP BY b.id ORDER BY b.id",
+            battletag_display("b"),
+            SCHEMA_TABLE_BATTLETAGS,
+            join_canonical("a", "b")
         ))?;
         let mapping = stmt.query_map(&[format!("%{}%", search)], |r| {
             let s: rusqlite::Result<String> = r.get(0);
@@ -101,19 +183,360 @@ impl MatchDb {
             .collect()
     }
 
-    pub fn record_match(&mut self, match_result: &Match) -> Result<(), MatchDbError> {
-        let sql = format!("INSERT INTO {} DEFAULT VALUES", SCHEMA_TABLE_MATCH);
-        let empty: &[&str] = &[];
+    /// Players with a battletag or alias containing `search`, most relevant first.
+    ///
+    /// Players are ranked by how many rounds they have played, discounted by how many
+    /// matches ago they last played, so regulars and recent guests both come first.
+    pub fn suggest_battletags(
+        &self,
+        search: &str,
+        limit: usize,
+    ) -> Result<Vec<BattleTag>, MatchDbError> {
+        let latest_match: Option<i64> = self.conn.query_row(
+            &format!("SELECT MAX(id) FROM {}", SCHEMA_TABLE_MATCH),
+            NO_PARAMS,
+            |r| r.get(0),
+        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}, COUNT(DISTINCT p.id), MAX(r.match_id) FROM {} a {} \
+             LEFT JOIN {} p ON p.battletag_id = b.id \
+             LEFT JOIN {} r ON r.id = p.round_id \
+             WHERE a.name LIKE (?1) \
+             GROUP BY b.id",
+            battletag_display("b"),
+            SCHEMA_TABLE_BATTLETAGS,
+            join_canonical("a", "b"),
+            SCHEMA_TABLE_PLAYS,
+            SCHEMA_TABLE_ROUND
+        ))?;
+        let rows = stmt.query_map(&[format!("%{}%", search)], |r| {
+            let row: rusqlite::Result<(String, i64, Option<i64>)> =
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?));
+            row
+        })?;
+
+        let mut ranked = Vec::new();
+        for row in rows {
+            let (name, rounds, last_match) = row?;
+            let matches_ago = match (latest_match, last_match) {
+                (Some(latest), Some(last)) => (latest - last) as f64,
+                _ => 0.0,
+            };
+            ranked.push((rounds as f64 / (1.0 + matches_ago), BattleTag::new(name)));
+        }
+        ranked.sort_by(|(a_score, a_name), (b_score, b_name)| {
+            b_score
+                .partial_cmp(a_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a_name.cmp(b_name))
+        });
+        Ok(ranked
+            .into_iter()
+            .take(limit)
+            .map(|(_, battletag)| battletag)
+            .collect())
+    }
+
+    /// Store a match and all of its rounds, returning the new match id.
+    ///
+    /// Any battletags in the match that have not been seen before are recorded as well.
+    /// Everything happens in a single transaction, so a failure leaves the database untouched.
+    pub fn record_match(&mut self, match_result: &Match) -> Result<i64, MatchDbError> {
+        self.record_team_match(match_result, None)
+    }
+
+    /// Store a match like `record_match`, noting which saved team played it.
+    pub fn record_team_match(
+        &mut self,
+        match_result: &Match,
+        team_id: Option<i64>,
+    ) -> Result<i64, MatchDbError> {
+        let sql = format!(
+            "INSERT INTO {} (rounds_to_win, max_rounds, hero_lock, tie_break, map, team_id) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            SCHEMA_TABLE_MATCH
+        );
+        let rules = match_result.rules();
 
         let tx = self.conn.transaction()?;
-        tx.execute(&sql, empty)?;
+        tx.execute(
+            &sql,
+            &[
+                Value::Integer(rules.rounds_to_win as i64),
+                Value::Integer(rules.max_rounds as i64),
+                Value::Text(rules.hero_lock.key().to_string()),
+                Value::Text(rules.tie_break.key().to_string()),
+                match_result
+                    .map()
+                    .map(|m| Value::Text(m.key().to_string()))
+                    .unwrap_or(Value::Null),
+                team_id.map(Value::Integer).unwrap_or(Value::Null),
+            ],
+        )?;
         let match_id = tx.last_insert_rowid();
         for round in match_result.iter() {
             record_round(tx.deref(), match_id, round)?;
         }
         tx.commit()?;
-        Ok(())
+        Ok(match_id)
+    }
+
+    pub fn load_match(&self, match_id: i64) -> Result<MatchRecord, MatchDbError> {
+        let (timestamp, rounds_to_win, max_rounds, hero_lock, tie_break, map, team_id): (
+            String,
+            i64,
+            i64,
+            String,
+            String,
+            Option<String>,
+            Option<i64>,
+        ) = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT timestamp, rounds_to_win, max_rounds, hero_lock, tie_break, map, \
+                     team_id FROM {} WHERE id = ?1",
+                    SCHEMA_TABLE_MATCH
+                ),
+                [match_id],
+                |r| {
+                    Ok((
+                        r.get(0)?,
+                        r.get(1)?,
+                        r.get(2)?,
+                        r.get(3)?,
+                        r.get(4)?,
+                        r.get(5)?,
+                        r.get(6)?,
+                    ))
+                },
+            )
+            .optional()?
+            .ok_or(MatchDbError::MatchDoesNotExist(match_id))?;
+        let rules = game_rules(rounds_to_win, max_rounds, &hero_lock, &tie_break)?;
+        let mut match_result = load_rounds(&self.conn, match_id, rules)?;
+        if let Some(map) = map {
+            match_result.set_map(Some(map.parse().map_err(MatchDbError::UnknownMap)?));
+        }
+        Ok(MatchRecord {
+            id: match_id,
+            timestamp,
+            team_id,
+            match_result,
+        })
+    }
+
+    /// List stored matches, newest first.
+    ///
+    /// `offset` skips that many of the newest matches, so consecutive pages can be
+    /// fetched by stepping `offset` by `limit`.
+    pub fn list_matches(&self, limit: u32, offset: u32) -> Result<Vec<MatchRecord>, MatchDbError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id FROM {} ORDER BY timestamp DESC, id DESC LIMIT ?1 OFFSET ?2",
+            SCHEMA_TABLE_MATCH
+        ))?;
+        let ids = stmt
+            .query_map([limit, offset], |r| r.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        ids.into_iter().map(|id| self.load_match(id)).collect()
+    }
+
+    pub fn match_count(&self) -> Result<i64, MatchDbError> {
+        Ok(self.conn.query_row(
+            &format!("SELECT count(*) FROM {}", SCHEMA_TABLE_MATCH),
+            NO_PARAMS,
+            |r| r.get(0),
+        )?)
+    }
+
+    /// Rewrite heroes stored under an id they have since been renamed from, returning how
+    /// many plays and enemy plays changed.
+    ///
+    /// Former ids still load, so this is optional; it keeps the stored keys current for
+    /// anything reading the database directly.
+    pub fn update_hero_ids(&mut self) -> Result<HeroIdUpdates, MatchDbError> {
+        let tx = self.conn.transaction()?;
+        let mut updated = HeroIdUpdates::default();
+        for hero in Hero::iter() {
+            for former_id in hero.former_ids() {
+                let params = [hero.blizzard_name(), former_id.as_str()];
+                updated.plays += tx.execute(
+                    &format!("UPDATE {} SET hero = ?1 WHERE hero = ?2", SCHEMA_TABLE_PLAYS),
+                    &params,
+                )?;
+                updated.enemy_plays += tx.execute(
+                    &format!("UPDATE {} SET hero = ?1 WHERE hero = ?2", SCHEMA_TABLE_ENEMY_PLAYS),
+                    &params,
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+}
+
+fn game_rules(
+    rounds_to_win: i64,
+    max_rounds: i64,
+    hero_lock: &str,
+    tie_break: &str,
+) -> Result<GameRules, MatchDbError> {
+    Ok(GameRules {
+        rounds_to_win: rounds_to_win as usize,
+        max_rounds: max_rounds as usize,
+        hero_lock: hero_lock.parse().map_err(MatchDbError::UnknownRule)?,
+        tie_break: tie_break.parse().map_err(MatchDbError::UnknownRule)?,
+    })
+}
+
+/// Rebuild a `Match` from its stored rounds, validating it along the way.
+fn load_rounds<C: Deref<Target = Connection>>(
+    conn: C,
+    match_id: i64,
+    rules: GameRules,
+) -> Result<Match, MatchDbError> {
+    let mut stmt = conn.prepare(&format!(
+        "\
+         SELECT r.id, r.outcome, {}, p.hero FROM {} r \
+         JOIN {} p ON p.round_id = r.id \
+         JOIN {} a ON a.id = p.battletag_id {} \
+         WHERE r.match_id = ?1 \
+         ORDER BY r.id, p.id",
+        battletag_display("b"),
+        SCHEMA_TABLE_ROUND,
+        SCHEMA_TABLE_PLAYS,
+        SCHEMA_TABLE_BATTLETAGS,
+        join_canonical("a", "b")
+    ))?;
+    let plays = stmt
+        .query_map([match_id], |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?))
+        })?
+        .collect::<Result<Vec<(i64, String, String, String)>, _>>()?;
+    let enemies = load_enemy_plays(conn.deref(), match_id)?;
+    build_match(match_id, rules, plays, enemies)
+}
+
+/// Put a `Match` together from its plays, as `(round id, outcome, battletag, hero)` in round
+/// and then player order, and its enemy comps keyed by round id.
+fn build_match(
+    match_id: i64,
+    rules: GameRules,
+    plays: Vec<(i64, String, String, String)>,
+    mut enemies: HashMap<i64, Vec<Hero>>,
+) -> Result<Match, MatchDbError> {
+    let mut m = Match::new(rules);
+    let mut plays = plays.into_iter().peekable();
+    while let Some((round_id, outcome, _, _)) = plays.peek() {
+        let round_id = *round_id;
+        let outcome = RoundOutcome::from_str(outcome).map_err(MatchDbError::UnknownOutcome)?;
+        let mut players = Vec::with_capacity(3);
+        while let Some((_, _, battletag, hero)) = plays.next_if(|p| p.0 == round_id) {
+            let hero = Hero::from_str(&hero).map_err(MatchDbError::UnknownHero)?;
+            players.push((BattleTag::new(battletag), hero));
+        }
+        if players.len() != 3 {
+            return Err(MatchDbError::IncompleteRound(round_id));
+        }
+        let enemy = match enemies.remove(&round_id).as_deref() {
+            None => None,
+            Some(&[e1, e2, e3]) => Some([e1, e2, e3]),
+            Some(_) => return Err(MatchDbError::IncompleteEnemyComp(round_id)),
+        };
+        let mut players = players.into_iter();
+        let round = Round {
+            player1: players.next().unwrap(),
+            player2: players.next().unwrap(),
+            player3: players.next().unwrap(),
+            outcome,
+            enemy,
+        };
+        m.insert_round(round)
+            .map_err(|e| MatchDbError::InvalidMatch(match_id, e))?;
+    }
+    Ok(m)
+}
+
+/// The recorded enemy heroes of each round in a match, keyed by round id and in slot order.
+fn load_enemy_plays<C: Deref<Target = Connection>>(
+    conn: C,
+    match_id: i64,
+) -> Result<HashMap<i64, Vec<Hero>>, MatchDbError> {
+    let mut stmt = conn.prepare(&format!(
+        "\
+         SELECT e.round_id, e.hero FROM {} e \
+         JOIN {} r ON r.id = e.round_id \
+         WHERE r.match_id = ?1 \
+         ORDER BY e.round_id, e.slot",
+        SCHEMA_TABLE_ENEMY_PLAYS, SCHEMA_TABLE_ROUND
+    ))?;
+    let plays = stmt
+        .query_map([match_id], |r| Ok((r.get(0)?, r.get(1)?)))?
+        .collect::<Result<Vec<(i64, String)>, _>>()?;
+
+    let mut enemies = HashMap::<i64, Vec<Hero>>::new();
+    for (round_id, hero) in plays {
+        let hero = Hero::from_str(&hero).map_err(MatchDbError::UnknownHero)?;
+        enemies.entry(round_id).or_default().push(hero);
     }
+    Ok(enemies)
+}
+
+/// Look a battletag up regardless of case, recording it if it is new.
+///
+/// Aliases resolve to the id of their player. A battletag stored before its casing was
+/// known takes the casing of `battletag`.
+fn get_or_insert_battletag_id<C: Deref<Target = Connection>>(
+    conn: C,
+    battletag: &BattleTag,
+) -> Result<i64, MatchDbError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, coalesce(canonical_id, id) from {} WHERE name = (?1)",
+        SCHEMA_TABLE_BATTLETAGS
+    ))?;
+
+    let existing_id = stmt
+        .query_row(&[battletag.key()], |r| {
+            let s: rusqlite::Result<(i64, i64)> = Ok((r.get(0)?, r.get(1)?));
+            s
+        })
+        .optional()?;
+    let (id, canonical_id) = match existing_id {
+        Some(ids) => ids,
+        None => return record_battletag(conn.deref(), battletag),
+    };
+    if battletag.as_str() != battletag.key() {
+        conn.execute(
+            &format!(
+                "UPDATE {} SET display_name = ?1 WHERE id = ?2 AND display_name IS NULL",
+                SCHEMA_TABLE_BATTLETAGS
+            ),
+            &[Value::Text(battletag.as_str().to_string()), Value::Integer(id)],
+        )?;
+    }
+    Ok(canonical_id)
+}
+
+/// New battletags must follow Blizzard's rules, see `BattleTag::parse`.
+fn record_battletag<C: Deref<Target = Connection>>(
+    conn: C,
+    battletag: &BattleTag,
+) -> Result<i64, MatchDbError> {
+    BattleTag::parse(battletag.as_str()).map_err(MatchDbError::InvalidBattletag)?;
+    let sql = format!(
+        "INSERT INTO {} (name, display_name) values (?1, ?2)",
+        SCHEMA_TABLE_BATTLETAGS
+    );
+    conn.execute(&sql, &[battletag.key(), battletag.as_str()]).map_err(|e| {
+        let mut new_e = None;
+        if let RusqliteError::SqliteFailure(ffierr, _) = e {
+            if let ErrorCode::ConstraintViolation = ffierr.code {
+                new_e = Some(MatchDbError::BattletagAlreadyExists(battletag.clone()))
+            }
+        }
+        new_e.unwrap_or_else(|| e.into())
+    })?;
+    Ok(conn.last_insert_rowid())
 }
 
 fn record_round<C: Deref<Target = Connection>>(
@@ -122,18 +545,38 @@ fn record_round<C: Deref<Target = Connection>>(
     round: &Round,
 ) -> Result<(), MatchDbError> {
     let sql = format!(
-        "INSERT INTO {} (match_id, is_win) VALUES (?1, ?2)",
+        "INSERT INTO {} (match_id, outcome) VALUES (?1, ?2)",
         SCHEMA_TABLE_ROUND
     );
     conn.execute(
         &sql,
-        &[Value::Integer(match_id), Value::Integer(round.win as i64)],
+        &[
+            Value::Integer(match_id),
+            Value::Text(round.outcome.key().to_string()),
+        ],
     )?;
     let round_id = conn.last_insert_rowid();
     for player in Player::iter() {
         let (bt, hero) = round.get_player(player);
+        get_or_insert_battletag_id(conn.deref(), bt)?;
         record_play(conn.deref(), round_id, bt, *hero)?;
     }
+    if let Some(enemy) = round.enemy {
+        let sql = format!(
+            "INSERT INTO {} (round_id, slot, hero) VALUES (?1, ?2, ?3)",
+            SCHEMA_TABLE_ENEMY_PLAYS
+        );
+        for slot in Player::iter() {
+            conn.execute(
+                &sql,
+                &[
+                    Value::Integer(round_id),
+                    Value::Integer(slot.index() as i64),
+                    Value::Text(enemy[slot.index()].blizzard_name().to_string()),
+                ],
+            )?;
+        }
+    }
     Ok(())
 }
 fn record_play<C: Deref<Target = Connection>>(
@@ -146,7 +589,7 @@ fn record_play<C: Deref<Target = Connection>>(
         "\
          INSERT INTO {} ( round_id, battletag_id, hero ) VALUES (\
          ?1,\
-         (SELECT id from {} WHERE name = ?2),\
+         (SELECT coalesce(canonical_id, id) from {} WHERE name = ?2),\
          ?3\
          )",
         SCHEMA_TABLE_PLAYS, SCHEMA_TABLE_BATTLETAGS
@@ -155,8 +598,8 @@ fn record_play<C: Deref<Target = Connection>>(
         &sql,
         &[
             Value::Integer(round_id),
-            Value::Text(battletag.as_str().into()),
-            Value::Text(format!("{}", hero.blizzard_name())),
+            Value::Text(battletag.key().into()),
+            Value::Text(hero.blizzard_name().to_string()),
         ],
     )
     .map_err(|e| {
@@ -173,15 +616,15 @@ fn record_play<C: Deref<Target = Connection>>(
 
 impl Default for MatchDb {
     fn default() -> MatchDb {
-        let conn = Connection::open_in_memory().unwrap();
-        create_schema(&conn).unwrap();
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrations::migrate(&mut conn).unwrap();
         MatchDb { conn }
     }
 }
 
 pub fn main() -> Result<(), failure::Error> {
-    let conn = Connection::open("bt.db")?;
-    create_schema(&conn)?;
+    let mut conn = Connection::open("bt.db")?;
+    migrations::migrate(&mut conn)?;
     let mut mdb = MatchDb::new(conn);
 
     let p1 = BattleTag::new("player1");
@@ -199,7 +642,7 @@ pub fn main() -> Result<(), failure::Error> {
     builder.set_player(Player::One, Hero::Mercy);
     builder.set_player(Player::Two, Hero::Torbjorn);
     builder.set_player(Player::Three, Hero::Pharah);
-    builder.set_win(true);
+    builder.set_outcome(RoundOutcome::Win);
     m.insert_round(builder.finalize()?)?;
     mdb.record_match(&m)?;
 
@@ -208,59 +651,28 @@ pub fn main() -> Result<(), failure::Error> {
     Ok(())
 }
 
-pub fn create_schema(conn: &Connection) -> Result<(), MatchDbError> {
-    conn.execute(
-        &format!(
-            "create table if not exists {} (
-             id integer primary key,
-             name text not null unique
-         )",
-            SCHEMA_TABLE_BATTLETAGS
-        ),
-        NO_PARAMS,
-    )?;
-    conn.execute(
-        &format!(
-            "create table if not exists {} (
-             id integer primary key,
-             timestamp datetime default CURRENT_TIMESTAMP
-         )",
-            SCHEMA_TABLE_MATCH
-        ),
-        NO_PARAMS,
-    )?;
-    conn.execute(
-        &format!(
-            "create table if not exists {} (
-             id integer primary key,
-             match_id integer not null,
-             is_win bool not null,
-             FOREIGN KEY(match_id) REFERENCES {}(id)
-         )",
-            SCHEMA_TABLE_ROUND, SCHEMA_TABLE_MATCH
-        ),
-        NO_PARAMS,
-    )?;
-    conn.execute(
-        &format!(
-            "create table if not exists {} (
-             id integer primary key,
-             round_id integer not null,
-             battletag_id integer not null,
-             hero TEXT not null,
-             FOREIGN KEY(round_id) REFERENCES {}(id),
-             FOREIGN KEY(battletag_id) REFERENCES {}(id)
-         )",
-            SCHEMA_TABLE_PLAYS, SCHEMA_TABLE_ROUND, SCHEMA_TABLE_BATTLETAGS
-        ),
-        NO_PARAMS,
-    )?;
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use overwatch::Map;
+
+    #[test]
+    fn create_then_open_existing() {
+        let path = std::env::temp_dir().join(format!("match-history-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        match open_existing(&path) {
+            Err(MatchDbError::DatabaseNotFound(_)) => {},
+            r => panic!("expected missing database: {:?}", r),
+        }
+        create(&path).unwrap();
+        match create(&path) {
+            Err(MatchDbError::DatabaseAlreadyExists(_)) => {},
+            r => panic!("expected existing database: {:?}", r),
+        }
+        open_existing(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
 
     #[test]
     fn case_insensitve_battletag_search() {
@@ -284,6 +696,39 @@ mod tests {
         assert_eq!(mixed_search, tags);
     }
 
+    #[test]
+    fn battletag_casing_kept_but_not_identity() {
+        let mut db = MatchDb::default();
+        let id = db
+            .record_battletag(&BattleTag::new("PlayerOne#1234"))
+            .unwrap();
+        let lookup = db
+            .get_or_insert_battletag_id(&BattleTag::new("playerone#1234"))
+            .unwrap();
+        assert_eq!(lookup, id);
+        match db.record_battletag(&BattleTag::new("PLAYERONE#1234")) {
+            Err(MatchDbError::BattletagAlreadyExists(_)) => {},
+            r => panic!("expected existing battletag error: {:?}", r),
+        }
+
+        let mut m = Match::default();
+        let mut builder = CompBuilder::new(Roster::new("playerONE#1234", "Player2", "player3"));
+        builder.set_player(Player::One, Hero::Ana);
+        builder.set_player(Player::Two, Hero::Mei);
+        builder.set_player(Player::Three, Hero::Sigma);
+        builder.set_outcome(RoundOutcome::Win);
+        m.insert_round(builder.finalize().unwrap()).unwrap();
+        let match_id = db.record_match(&m).unwrap();
+        assert_eq!(count_rows(&db, SCHEMA_TABLE_BATTLETAGS), 3);
+
+        let record = db.load_match(match_id).unwrap();
+        let round = record.match_result.iter().next().unwrap();
+        assert_eq!(round.player1.0.as_str(), "PlayerOne#1234");
+        assert_eq!(round.player2.0.as_str(), "Player2");
+        let search = db.search_battletags("playerone").unwrap();
+        assert_eq!(search[0].as_str(), "PlayerOne#1234");
+    }
+
     #[test]
     fn repeat_insert_battletag_custom_error() {
         let db = MatchDb::default();
@@ -301,4 +746,234 @@ mod tests {
             },
         }
     }
+
+    #[test]
+    fn record_battletag_rejects_invalid() {
+        let db = MatchDb::default();
+        match db.record_battletag(&BattleTag::new("ana mei")) {
+            Err(MatchDbError::InvalidBattletag(ParseBattleTagError::InvalidCharacter(_, c))) => {
+                assert_eq!(c, ' ')
+            },
+            r => panic!("expected invalid battletag error: {:?}", r),
+        }
+        assert!(db.search_battletags("ana").unwrap().is_empty());
+        db.record_battletag(&BattleTag::new("Ana#1234")).unwrap();
+    }
+
+    fn test_match() -> Match {
+        let mut m = Match::default();
+        let mut builder = CompBuilder::new(Roster::new("player1", "player2", "player3"));
+        builder.set_player(Player::One, Hero::Mercy);
+        builder.set_player(Player::Two, Hero::Torbjorn);
+        builder.set_player(Player::Three, Hero::Pharah);
+        builder.set_outcome(RoundOutcome::Win);
+        m.insert_round(builder.finalize().unwrap()).unwrap();
+        m
+    }
+
+    fn count_rows(db: &MatchDb, table: &str) -> i64 {
+        db.conn
+            .query_row(&format!("SELECT count(*) FROM {}", table), NO_PARAMS, |r| {
+                r.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn suggest_regular_and_recent_players_first() {
+        let mut db = MatchDb::default();
+        db.record_battletag(&BattleTag::new("playernew")).unwrap();
+        // player1 to player3 play a round, then player1 plays again with newcomers
+        db.record_match(&test_match()).unwrap();
+
+        let mut m = Match::default();
+        let mut builder = CompBuilder::new(Roster::new("player1", "player4", "player5"));
+        builder.set_player(Player::One, Hero::Ana);
+        builder.set_player(Player::Two, Hero::Mei);
+        builder.set_player(Player::Three, Hero::Sigma);
+        builder.set_outcome(RoundOutcome::Loss);
+        m.insert_round(builder.finalize().unwrap()).unwrap();
+        db.record_match(&m).unwrap();
+
+        let names = db
+            .suggest_battletags("player", 10)
+            .unwrap()
+            .into_iter()
+            .map(|bt| bt.as_str().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["player1", "player4", "player5", "player2", "player3", "playernew"]
+        );
+        assert_eq!(db.suggest_battletags("4", 10).unwrap(), vec![BattleTag::new("player4")]);
+        assert_eq!(db.suggest_battletags("player", 2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn record_match_registers_new_battletags() {
+        let mut db = MatchDb::default();
+        db.record_battletag(&BattleTag::new("player1")).unwrap();
+
+        let match_id = db.record_match(&test_match()).unwrap();
+        assert_eq!(match_id, 1);
+        assert_eq!(count_rows(&db, SCHEMA_TABLE_BATTLETAGS), 3);
+        assert_eq!(count_rows(&db, SCHEMA_TABLE_ROUND), 1);
+        assert_eq!(count_rows(&db, SCHEMA_TABLE_PLAYS), 3);
+    }
+
+    #[test]
+    fn failed_record_match_rolls_back() {
+        let mut db = MatchDb::default();
+        db.conn
+            .execute(&format!("DROP TABLE {}", SCHEMA_TABLE_PLAYS), NO_PARAMS)
+            .unwrap();
+
+        assert!(db.record_match(&test_match()).is_err());
+        assert_eq!(count_rows(&db, SCHEMA_TABLE_MATCH), 0);
+        assert_eq!(count_rows(&db, SCHEMA_TABLE_ROUND), 0);
+        assert_eq!(count_rows(&db, SCHEMA_TABLE_BATTLETAGS), 0);
+    }
+
+    #[test]
+    fn load_recorded_match() {
+        let mut db = MatchDb::default();
+        let m = test_match();
+        let match_id = db.record_match(&m).unwrap();
+
+        let record = db.load_match(match_id).unwrap();
+        assert_eq!(record.id, match_id);
+        assert_eq!(record.match_result, m);
+    }
+
+    #[test]
+    fn load_match_rules() {
+        let mut db = MatchDb::default();
+        let mut m = Match::new(GameRules::elimination());
+        for _ in 0..2 {
+            m.insert_round(test_match().iter().next().unwrap().clone())
+                .unwrap();
+        }
+        let match_id = db.record_match(&m).unwrap();
+
+        let record = db.load_match(match_id).unwrap();
+        assert_eq!(record.match_result.rules(), &GameRules::elimination());
+        assert_eq!(record.match_result, m);
+    }
+
+    #[test]
+    fn load_drawn_round() {
+        let mut db = MatchDb::default();
+        let mut m = Match::default();
+        let mut round = test_match().iter().next().unwrap().clone();
+        round.outcome = RoundOutcome::Draw;
+        m.insert_round(round).unwrap();
+        let match_id = db.record_match(&m).unwrap();
+
+        assert_eq!(db.load_match(match_id).unwrap().match_result, m);
+    }
+
+    #[test]
+    fn load_enemy_comp() {
+        let mut db = MatchDb::default();
+        let mut m = Match::default();
+        let mut round = test_match().iter().next().unwrap().clone();
+        round.enemy = Some([Hero::Ana, Hero::Reinhardt, Hero::Cassidy]);
+        m.insert_round(round).unwrap();
+        let match_id = db.record_match(&m).unwrap();
+        assert_eq!(count_rows(&db, SCHEMA_TABLE_ENEMY_PLAYS), 3);
+
+        assert_eq!(db.load_match(match_id).unwrap().match_result, m);
+    }
+
+    #[test]
+    fn load_match_map() {
+        let mut db = MatchDb::default();
+        let mut m = test_match();
+        m.set_map(Some(Map::Necropolis));
+        let match_id = db.record_match(&m).unwrap();
+
+        let record = db.load_match(match_id).unwrap();
+        assert_eq!(record.match_result.map(), Some(Map::Necropolis));
+        assert_eq!(record.match_result, m);
+    }
+
+    #[test]
+    fn load_missing_match() {
+        let db = MatchDb::default();
+        match db.load_match(42) {
+            Err(MatchDbError::MatchDoesNotExist(42)) => {},
+            r => panic!("expected missing match error: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn update_renamed_hero_ids() {
+        let mut db = MatchDb::default();
+        let m = test_match();
+        let match_id = db.record_match(&m).unwrap();
+        // As written before Blizzard renamed McCree
+        db.conn
+            .execute_batch(&format!(
+                "UPDATE {} SET hero = 'mccree' WHERE id = 1; \
+                 INSERT INTO {} (round_id, slot, hero) \
+                 VALUES (1, 0, 'mccree'), (1, 1, 'ana'), (1, 2, 'mei');",
+                SCHEMA_TABLE_PLAYS, SCHEMA_TABLE_ENEMY_PLAYS
+            ))
+            .unwrap();
+        let round = db.load_match(match_id).unwrap().match_result.iter().next().cloned();
+        assert_eq!(round.unwrap().player1.1, Hero::Cassidy);
+
+        let updated = db.update_hero_ids().unwrap();
+        assert_eq!((updated.plays, updated.enemy_plays), (1, 1));
+        assert_eq!(db.update_hero_ids().unwrap(), HeroIdUpdates::default());
+        let stored: String = db
+            .conn
+            .query_row(
+                &format!("SELECT hero FROM {} WHERE id = 1", SCHEMA_TABLE_PLAYS),
+                NO_PARAMS,
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored, "cassidy");
+        let round = db.load_match(match_id).unwrap().match_result.iter().next().cloned();
+        assert_eq!(round.unwrap().player1.1, Hero::Cassidy);
+    }
+
+    #[test]
+    fn load_unknown_hero() {
+        let mut db = MatchDb::default();
+        let match_id = db.record_match(&test_match()).unwrap();
+        db.conn
+            .execute(
+                &format!("UPDATE {} SET hero = 'pharmacy'", SCHEMA_TABLE_PLAYS),
+                NO_PARAMS,
+            )
+            .unwrap();
+        match db.load_match(match_id) {
+            Err(MatchDbError::UnknownHero(_)) => {},
+            r => panic!("expected unknown hero error: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn list_matches_newest_first() {
+        let mut db = MatchDb::default();
+        let ids = (0..5)
+            .map(|_| db.record_match(&test_match()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(db.match_count().unwrap(), 5);
+
+        let first_page = db.list_matches(2, 0).unwrap();
+        assert_eq!(
+            first_page.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![ids[4], ids[3]]
+        );
+        assert_eq!(first_page[0].match_result, test_match());
+
+        let last_page = db.list_matches(2, 4).unwrap();
+        assert_eq!(
+            last_page.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![ids[0]]
+        );
+    }
 }
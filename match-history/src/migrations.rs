@@ -0,0 +1,486 @@
+use crate::{
+    MatchDbError, SCHEMA_TABLE_BATTLETAGS, SCHEMA_TABLE_ENEMY_PLAYS, SCHEMA_TABLE_MATCH,
+    SCHEMA_TABLE_PLAYS, SCHEMA_TABLE_ROUND, SCHEMA_TABLE_TEAMS,
+};
+use overwatch::BattleTag;
+use rusqlite::types::Value;
+use rusqlite::{Connection, NO_PARAMS};
+use std::collections::BTreeMap;
+
+/// The schema version written by this build.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+struct Migration {
+    description: &'static str,
+    apply: fn(&Connection) -> Result<(), MatchDbError>,
+}
+
+/// Every schema change ever made, in order.
+///
+/// The database's `user_version` is the number of these that have been applied,
+/// so entries must never be edited or reordered once released; add a new one instead.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "initial schema",
+        apply: initial_schema,
+    },
+    Migration {
+        description: "store game rules per match",
+        apply: match_rules,
+    },
+    Migration {
+        description: "replace round is_win with an outcome",
+        apply: round_outcomes,
+    },
+    Migration {
+        description: "record enemy compositions",
+        apply: enemy_plays,
+    },
+    Migration {
+        description: "store the map per match",
+        apply: match_map,
+    },
+    Migration {
+        description: "store saved teams",
+        apply: teams,
+    },
+    Migration {
+        description: "keep battletag display names",
+        apply: battletag_display_names,
+    },
+    Migration {
+        description: "resolve battletag aliases",
+        apply: battletag_aliases,
+    },
+];
+
+pub fn schema_version(conn: &Connection) -> Result<u32, MatchDbError> {
+    Ok(conn.pragma_query_value(None, "user_version", |r| r.get(0))?)
+}
+
+/// Bring the database up to `SCHEMA_VERSION`.
+///
+/// Each migration runs in its own transaction along with the version bump, so an
+/// interrupted upgrade leaves the database at the last version that fully applied.
+/// Databases written by a newer build are refused rather than guessed at.
+pub fn migrate(conn: &mut Connection) -> Result<(), MatchDbError> {
+    let version = schema_version(conn)?;
+    if version > SCHEMA_VERSION {
+        return Err(MatchDbError::SchemaTooNew(version, SCHEMA_VERSION));
+    }
+
+    for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let target = idx as u32 + 1;
+        info!(
+            "migrating database to version {}: {}",
+            target, migration.description
+        );
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx)?;
+        tx.pragma_update(None, "user_version", &target)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Databases from before versioning have `user_version` 0 but may already hold
+/// these tables, which is why this is the only migration allowed `if not exists`.
+fn initial_schema(conn: &Connection) -> Result<(), MatchDbError> {
+    conn.execute(
+        &format!(
+            "create table if not exists {} (
+             id integer primary key,
+             name text not null unique
+         )",
+            SCHEMA_TABLE_BATTLETAGS
+        ),
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        &format!(
+            "create table if not exists {} (
+             id integer primary key,
+             timestamp datetime default CURRENT_TIMESTAMP
+         )",
+            SCHEMA_TABLE_MATCH
+        ),
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        &format!(
+            "create table if not exists {} (
+             id integer primary key,
+             match_id integer not null,
+             is_win bool not null,
+             FOREIGN KEY(match_id) REFERENCES {}(id)
+         )",
+            SCHEMA_TABLE_ROUND, SCHEMA_TABLE_MATCH
+        ),
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        &format!(
+            "create table if not exists {} (
+             id integer primary key,
+             round_id integer not null,
+             battletag_id integer not null,
+             hero TEXT not null,
+             FOREIGN KEY(round_id) REFERENCES {}(id),
+             FOREIGN KEY(battletag_id) REFERENCES {}(id)
+         )",
+            SCHEMA_TABLE_PLAYS, SCHEMA_TABLE_ROUND, SCHEMA_TABLE_BATTLETAGS
+        ),
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+/// Matches recorded before this were all played under the lockout rules.
+fn match_rules(conn: &Connection) -> Result<(), MatchDbError> {
+    for column in &[
+        "rounds_to_win integer not null default 3",
+        "max_rounds integer not null default 5",
+        "hero_lock text not null default 'winners'",
+        "tie_break text not null default 'draw'",
+    ] {
+        conn.execute(
+            &format!("alter table {} add column {}", SCHEMA_TABLE_MATCH, column),
+            NO_PARAMS,
+        )?;
+    }
+    Ok(())
+}
+
+/// Sqlite can't drop columns, so the rounds table is rebuilt with `outcome` in
+/// place of `is_win`, keeping the round ids that plays refer to.
+fn round_outcomes(conn: &Connection) -> Result<(), MatchDbError> {
+    conn.execute(
+        &format!(
+            "create table rounds_new (
+             id integer primary key,
+             match_id integer not null,
+             outcome text not null,
+             FOREIGN KEY(match_id) REFERENCES {}(id)
+         )",
+            SCHEMA_TABLE_MATCH
+        ),
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        &format!(
+            "insert into rounds_new (id, match_id, outcome)
+             select id, match_id, case when is_win then 'win' else 'loss' end from {}",
+            SCHEMA_TABLE_ROUND
+        ),
+        NO_PARAMS,
+    )?;
+    conn.execute(&format!("drop table {}", SCHEMA_TABLE_ROUND), NO_PARAMS)?;
+    conn.execute(
+        &format!("alter table rounds_new rename to {}", SCHEMA_TABLE_ROUND),
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn enemy_plays(conn: &Connection) -> Result<(), MatchDbError> {
+    conn.execute(
+        &format!(
+            "create table {} (
+             id integer primary key,
+             round_id integer not null,
+             slot integer not null,
+             hero TEXT not null,
+             UNIQUE(round_id, slot),
+             FOREIGN KEY(round_id) REFERENCES {}(id)
+         )",
+            SCHEMA_TABLE_ENEMY_PLAYS, SCHEMA_TABLE_ROUND
+        ),
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+/// The map is left null for matches recorded before this, as it is unknown.
+fn match_map(conn: &Connection) -> Result<(), MatchDbError> {
+    conn.execute(
+        &format!("alter table {} add column map text", SCHEMA_TABLE_MATCH),
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+/// Matches recorded before this have no team.
+fn teams(conn: &Connection) -> Result<(), MatchDbError> {
+    conn.execute(
+        &format!(
+            "create table {} (
+             id integer primary key,
+             name text not null unique,
+             player1_id integer not null,
+             player2_id integer not null,
+             player3_id integer not null,
+             FOREIGN KEY(player1_id) REFERENCES {1}(id),
+             FOREIGN KEY(player2_id) REFERENCES {1}(id),
+             FOREIGN KEY(player3_id) REFERENCES {1}(id)
+         )",
+            SCHEMA_TABLE_TEAMS, SCHEMA_TABLE_BATTLETAGS
+        ),
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        &format!(
+            "alter table {} add column team_id integer REFERENCES {}(id)",
+            SCHEMA_TABLE_MATCH, SCHEMA_TABLE_TEAMS
+        ),
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+/// Battletags used to be lowercased before they were stored, so only ones written before
+/// that still have their capitals to recover; the rest stay null until the player is entered
+/// again. Spellings that only differ by case are the same player now, so they are folded into
+/// the oldest one, keeping the first capitalised spelling as its display name.
+///
+/// Names are folded with `BattleTag::key` rather than sqlite's `lower`, which only knows ASCII.
+fn battletag_display_names(conn: &Connection) -> Result<(), MatchDbError> {
+    conn.execute(
+        &format!(
+            "alter table {} add column display_name text",
+            SCHEMA_TABLE_BATTLETAGS
+        ),
+        NO_PARAMS,
+    )?;
+
+    let rows = conn
+        .prepare(&format!(
+            "select id, name from {} order by id",
+            SCHEMA_TABLE_BATTLETAGS
+        ))?
+        .query_map(NO_PARAMS, |r| Ok((r.get(0)?, r.get(1)?)))?
+        .collect::<Result<Vec<(i64, String)>, _>>()?;
+    let mut players = BTreeMap::<String, Vec<(i64, String)>>::new();
+    for (id, name) in rows {
+        let key = BattleTag::new(name.as_str()).key().to_string();
+        players.entry(key).or_default().push((id, name));
+    }
+
+    for (key, spellings) in players {
+        let player_id = spellings[0].0;
+        for (id, _) in &spellings[1..] {
+            conn.execute(
+                &format!(
+                    "update {} set battletag_id = ?2 where battletag_id = ?1",
+                    SCHEMA_TABLE_PLAYS
+                ),
+                [*id, player_id],
+            )?;
+            for column in &["player1_id", "player2_id", "player3_id"] {
+                conn.execute(
+                    &format!(
+                        "update {0} set {1} = ?2 where {1} = ?1",
+                        SCHEMA_TABLE_TEAMS, column
+                    ),
+                    [*id, player_id],
+                )?;
+            }
+            conn.execute(
+                &format!("delete from {} where id = ?1", SCHEMA_TABLE_BATTLETAGS),
+                [*id],
+            )?;
+        }
+        let display_name = spellings
+            .iter()
+            .map(|(_, name)| name)
+            .find(|name| **name != key);
+        conn.execute(
+            &format!(
+                "update {} set name = ?2, display_name = ?3 where id = ?1",
+                SCHEMA_TABLE_BATTLETAGS
+            ),
+            &[
+                Value::Integer(player_id),
+                Value::Text(key.clone()),
+                display_name
+                    .cloned()
+                    .map(Value::Text)
+                    .unwrap_or(Value::Null),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Every battletag stored so far is its own player.
+fn battletag_aliases(conn: &Connection) -> Result<(), MatchDbError> {
+    conn.execute(
+        &format!(
+            "alter table {0} add column canonical_id integer REFERENCES {0}(id)",
+            SCHEMA_TABLE_BATTLETAGS
+        ),
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MatchDb;
+    use crate::test_util::round;
+    use overwatch::overwatch_3v3::{Match, RoundOutcome};
+    use overwatch::Hero;
+
+    /// A snapshot of a database as written by each released schema version.
+    ///
+    /// Every fixture holds the same match, so they can all be checked against `fixture_match`.
+    const FIXTURES: &[(u32, &str)] = &[
+        (0, include_str!("../fixtures/v0.sql")),
+        (1, include_str!("../fixtures/v1.sql")),
+        (2, include_str!("../fixtures/v2.sql")),
+        (3, include_str!("../fixtures/v3.sql")),
+        (4, include_str!("../fixtures/v4.sql")),
+        (5, include_str!("../fixtures/v5.sql")),
+        (6, include_str!("../fixtures/v6.sql")),
+        (7, include_str!("../fixtures/v7.sql")),
+    ];
+
+    fn fixture_match() -> Match {
+        let mut m = Match::default();
+        for r in [
+            round([Hero::Ana, Hero::Zenyatta, Hero::Sigma], RoundOutcome::Loss),
+            round([Hero::Roadhog, Hero::Brigitte, Hero::Mei], RoundOutcome::Win),
+            round([Hero::Pharah, Hero::Soldier76, Hero::Mercy], RoundOutcome::Win),
+            round([Hero::Cassidy, Hero::Reinhardt, Hero::Lucio], RoundOutcome::Win),
+        ]
+        .iter()
+        .cloned()
+        {
+            m.insert_round(r).unwrap();
+        }
+        m
+    }
+
+    #[test]
+    fn fixtures_cover_every_version() {
+        for version in 0..SCHEMA_VERSION {
+            assert!(
+                FIXTURES.iter().any(|(v, _)| *v == version),
+                "no fixture for schema version {}",
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn upgrade_fixtures() {
+        for (version, sql) in FIXTURES {
+            let mut conn = Connection::open_in_memory().unwrap();
+            conn.execute_batch(sql).unwrap();
+            assert_eq!(schema_version(&conn).unwrap(), *version);
+
+            migrate(&mut conn).unwrap();
+            assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+
+            let db = MatchDb::new(conn);
+            let record = db.load_match(1).unwrap();
+            assert_eq!(record.timestamp, "2019-11-02 20:15:00");
+            assert_eq!(record.match_result, fixture_match());
+        }
+    }
+
+    #[test]
+    fn update_hero_ids_of_old_fixtures() {
+        for (_, sql) in FIXTURES {
+            let mut conn = Connection::open_in_memory().unwrap();
+            conn.execute_batch(sql).unwrap();
+            migrate(&mut conn).unwrap();
+
+            let mut db = MatchDb::new(conn);
+            assert_eq!(db.update_hero_ids().unwrap().plays, 1);
+            let record = db.load_match(1).unwrap();
+            assert_eq!(record.match_result, fixture_match());
+        }
+    }
+
+    #[test]
+    fn recover_battletag_display_names() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(include_str!("../fixtures/v6.sql"))
+            .unwrap();
+        conn.execute_batch(
+            "update battletags set name = 'Player1' where name = 'player1';
+             insert into battletags (id, name) values
+                 (4, 'Player4'), (5, 'PLAYER4'), (6, 'PLAYER2'), (7, 'ÉCLAIR'), (8, 'éclair');
+             update plays set battletag_id = 6 where id in (2, 5);
+             insert into teams values (1, 'team', 1, 6, 3);",
+        )
+        .unwrap();
+        migrate(&mut conn).unwrap();
+
+        let names = conn
+            .prepare("select name, display_name from battletags order by id")
+            .unwrap()
+            .query_map(NO_PARAMS, |r| {
+                Ok((r.get::<_, String>(0)?, r.get::<_, Option<String>>(1)?))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let expected = [
+            ("player1", Some("Player1")),
+            ("player2", Some("PLAYER2")),
+            ("player3", None),
+            ("player4", Some("Player4")),
+            ("éclair", Some("ÉCLAIR")),
+        ];
+        assert_eq!(names.len(), expected.len());
+        for ((name, display), (expected_name, expected_display)) in names.iter().zip(&expected) {
+            assert_eq!(name, expected_name);
+            assert_eq!(display.as_ref().map(String::as_str), *expected_display);
+        }
+
+        let merged = conn
+            .query_row(
+                "select count(*) from plays where battletag_id = 2",
+                NO_PARAMS,
+                |r| r.get::<_, i64>(0),
+            )
+            .unwrap();
+        assert_eq!(merged, 4);
+        let team_player = conn
+            .query_row("select player2_id from teams", NO_PARAMS, |r| {
+                r.get::<_, i64>(0)
+            })
+            .unwrap();
+        assert_eq!(team_player, 2);
+
+        let record = MatchDb::new(conn).load_match(1).unwrap();
+        assert_eq!(record.match_result, fixture_match());
+        let round = record.match_result.iter().next().unwrap();
+        assert_eq!(round.player1.0.as_str(), "Player1");
+        assert_eq!(round.player2.0.as_str(), "PLAYER2");
+    }
+
+    #[test]
+    fn migrate_is_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn refuse_newer_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", &(SCHEMA_VERSION + 1))
+            .unwrap();
+        match migrate(&mut conn) {
+            Err(MatchDbError::SchemaTooNew(found, supported)) => {
+                assert_eq!(found, SCHEMA_VERSION + 1);
+                assert_eq!(supported, SCHEMA_VERSION);
+            },
+            r => panic!("expected schema too new error: {:?}", r),
+        }
+    }
+}
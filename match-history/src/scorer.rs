@@ -0,0 +1,128 @@
+use crate::analytics::{CompSize, WinRate};
+use crate::{MatchDb, MatchDbError};
+use overwatch::overwatch_3v3::{CompScorer, Player, Roster};
+use overwatch::{BattleTag, Hero};
+use std::collections::HashMap;
+
+/// How many rounds of evidence the prior is worth when shrinking a win rate.
+const PRIOR_ROUNDS: f64 = 3.0;
+
+/// Scores comps by their expected round win rate, estimated from the match history.
+///
+/// Each estimate is shrunk towards the level above it: overall results, then the
+/// hero, then that player on that hero, then the exact trio. Comps we have never
+/// played still get a sensible score from their parts, and a couple of lucky rounds
+/// don't outweigh a long record.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryScorer {
+    baseline: WinRate,
+    heros: HashMap<Hero, WinRate>,
+    /// Keyed by `BattleTag::key`, so scoring can look players up without allocating.
+    player_heros: HashMap<String, HashMap<Hero, WinRate>>,
+    /// Heroes sorted, as in `CompWinRate`.
+    trios: HashMap<[Hero; 3], WinRate>,
+}
+
+impl MatchDb {
+    pub fn comp_scorer(&self) -> Result<HistoryScorer, MatchDbError> {
+        let trios = self.comp_win_rates(CompSize::Trio, 0)?;
+        let mut player_heros = HashMap::<String, HashMap<Hero, WinRate>>::new();
+        for r in self.player_hero_win_rates()? {
+            player_heros
+                .entry(r.battletag.key().to_string())
+                .or_default()
+                .insert(r.hero, r.win_rate);
+        }
+        Ok(HistoryScorer {
+            baseline: trios.baseline,
+            heros: self
+                .hero_win_rates()?
+                .into_iter()
+                .map(|r| (r.hero, r.win_rate))
+                .collect(),
+            player_heros,
+            trios: trios
+                .comps
+                .into_iter()
+                .map(|c| ([c.heros[0], c.heros[1], c.heros[2]], c.win_rate))
+                .collect(),
+        })
+    }
+}
+
+impl HistoryScorer {
+    fn player_hero(&self, battletag: &BattleTag, hero: Hero) -> f64 {
+        let baseline = shrink(self.baseline, 0.5);
+        let hero_rate = shrink(lookup(&self.heros, &hero), baseline);
+        let player_rate = self
+            .player_heros
+            .get(battletag.key())
+            .map(|heros| lookup(heros, &hero))
+            .unwrap_or_default();
+        shrink(player_rate, hero_rate)
+    }
+}
+
+impl CompScorer for HistoryScorer {
+    fn score(&self, roster: &Roster, comp: [Hero; 3]) -> f64 {
+        let Roster(p1, p2, p3) = roster;
+        let parts = [p1, p2, p3]
+            .iter()
+            .zip(Player::iter())
+            .map(|(bt, p)| self.player_hero(bt, comp[p.index()]))
+            .sum::<f64>()
+            / 3.0;
+        let mut trio = comp;
+        trio.sort();
+        shrink(lookup(&self.trios, &trio), parts)
+    }
+}
+
+fn lookup<K: std::hash::Hash + Eq>(rates: &HashMap<K, WinRate>, key: &K) -> WinRate {
+    rates.get(key).cloned().unwrap_or_default()
+}
+
+/// Blend an observed win rate with a prior, weighting the prior as `PRIOR_ROUNDS` rounds.
+fn shrink(observed: WinRate, prior: f64) -> f64 {
+    (f64::from(observed.wins) + PRIOR_ROUNDS * prior) / (f64::from(observed.rounds()) + PRIOR_ROUNDS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{record, round};
+    use overwatch::overwatch_3v3::{recommend, CompBuilder, Match, RoundOutcome};
+
+    #[test]
+    fn empty_history_is_neutral() {
+        let scorer = HistoryScorer::default();
+        let roster = Roster::default();
+        assert_eq!(scorer.score(&roster, [Hero::Ana, Hero::Mei, Hero::Sigma]), 0.5);
+    }
+
+    #[test]
+    fn winning_comp_recommended() {
+        let mut db = MatchDb::default();
+        for _ in 0..4 {
+            let won = round([Hero::Ana, Hero::Mei, Hero::Sigma], RoundOutcome::Win);
+            record(&mut db, None, &[won]);
+            let lost = round([Hero::Mercy, Hero::Pharah, Hero::Reinhardt], RoundOutcome::Loss);
+            record(&mut db, None, &[lost]);
+        }
+        let scorer = db.comp_scorer().unwrap();
+        let roster = Roster::new("player1", "player2", "player3");
+
+        let winning = scorer.score(&roster, [Hero::Ana, Hero::Mei, Hero::Sigma]);
+        let untried = scorer.score(&roster, [Hero::Genji, Hero::Lucio, Hero::Winston]);
+        let losing = scorer.score(&roster, [Hero::Mercy, Hero::Pharah, Hero::Reinhardt]);
+        assert!(winning > untried && untried > losing);
+
+        // the same heroes on the wrong players are only partly credited
+        let swapped = scorer.score(&roster, [Hero::Sigma, Hero::Ana, Hero::Mei]);
+        assert!(winning > swapped && swapped > untried);
+
+        let builder = CompBuilder::new(roster);
+        let suggestions = recommend(&scorer, &Match::default(), &builder, 3);
+        assert_eq!(suggestions[0].comp, [Hero::Ana, Hero::Mei, Hero::Sigma]);
+    }
+}
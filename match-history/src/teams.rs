@@ -0,0 +1,239 @@
+//! Named rosters, so a regular trio can be picked in one go instead of retyped.
+
+use crate::{battletag_display, get_or_insert_battletag_id, MatchDb, MatchDbError};
+use crate::{SCHEMA_TABLE_BATTLETAGS, SCHEMA_TABLE_MATCH, SCHEMA_TABLE_TEAMS};
+use overwatch::overwatch_3v3::Roster;
+use overwatch::BattleTag;
+use rusqlite::types::Value;
+use rusqlite::Error as RusqliteError;
+use rusqlite::{Connection, ErrorCode, OptionalExtension, Row, NO_PARAMS};
+use std::ops::Deref;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Team {
+    pub id: i64,
+    pub name: String,
+    pub roster: Roster,
+}
+
+impl MatchDb {
+    /// Save `roster` under `name`, recording any battletags not seen before.
+    pub fn create_team(&mut self, name: &str, roster: &Roster) -> Result<Team, MatchDbError> {
+        let tx = self.conn.transaction()?;
+        let ids = roster_ids(tx.deref(), roster)?;
+        tx.execute(
+            &format!(
+                "INSERT INTO {} (name, player1_id, player2_id, player3_id) \
+                 VALUES (?1, ?2, ?3, ?4)",
+                SCHEMA_TABLE_TEAMS
+            ),
+            &[
+                Value::Text(name.to_string()),
+                Value::Integer(ids[0]),
+                Value::Integer(ids[1]),
+                Value::Integer(ids[2]),
+            ],
+        )
+        .map_err(|e| name_conflict(e, name))?;
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(Team {
+            id,
+            name: name.to_string(),
+            roster: roster.clone(),
+        })
+    }
+
+    /// Every saved team, by name.
+    pub fn list_teams(&self) -> Result<Vec<Team>, MatchDbError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{} ORDER BY t.name, t.id", select_teams()))?;
+        let teams = stmt
+            .query_map(NO_PARAMS, team_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(teams)
+    }
+
+    pub fn get_team(&self, team_id: i64) -> Result<Team, MatchDbError> {
+        self.conn
+            .query_row(
+                &format!("{} WHERE t.id = ?1", select_teams()),
+                [team_id],
+                team_from_row,
+            )
+            .optional()?
+            .ok_or(MatchDbError::TeamDoesNotExist(team_id))
+    }
+
+    /// Rename a team and replace its players, keeping the matches it already played.
+    pub fn update_team(
+        &mut self,
+        team_id: i64,
+        name: &str,
+        roster: &Roster,
+    ) -> Result<Team, MatchDbError> {
+        let tx = self.conn.transaction()?;
+        let ids = roster_ids(tx.deref(), roster)?;
+        let updated = tx
+            .execute(
+                &format!(
+                    "UPDATE {} SET name = ?1, player1_id = ?2, player2_id = ?3, player3_id = ?4 \
+                     WHERE id = ?5",
+                    SCHEMA_TABLE_TEAMS
+                ),
+                &[
+                    Value::Text(name.to_string()),
+                    Value::Integer(ids[0]),
+                    Value::Integer(ids[1]),
+                    Value::Integer(ids[2]),
+                    Value::Integer(team_id),
+                ],
+            )
+            .map_err(|e| name_conflict(e, name))?;
+        if updated == 0 {
+            return Err(MatchDbError::TeamDoesNotExist(team_id));
+        }
+        tx.commit()?;
+        Ok(Team {
+            id: team_id,
+            name: name.to_string(),
+            roster: roster.clone(),
+        })
+    }
+
+    /// Forget a team. Its matches stay in the history, just no longer grouped under it.
+    pub fn delete_team(&mut self, team_id: i64) -> Result<(), MatchDbError> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            &format!(
+                "UPDATE {} SET team_id = NULL WHERE team_id = ?1",
+                SCHEMA_TABLE_MATCH
+            ),
+            [team_id],
+        )?;
+        let deleted = tx.execute(
+            &format!("DELETE FROM {} WHERE id = ?1", SCHEMA_TABLE_TEAMS),
+            [team_id],
+        )?;
+        if deleted == 0 {
+            return Err(MatchDbError::TeamDoesNotExist(team_id));
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+fn select_teams() -> String {
+    format!(
+        "SELECT t.id, t.name, {}, {}, {} FROM {} t \
+         JOIN {4} p1 ON p1.id = t.player1_id \
+         JOIN {4} p2 ON p2.id = t.player2_id \
+         JOIN {4} p3 ON p3.id = t.player3_id",
+        battletag_display("p1"),
+        battletag_display("p2"),
+        battletag_display("p3"),
+        SCHEMA_TABLE_TEAMS,
+        SCHEMA_TABLE_BATTLETAGS
+    )
+}
+
+fn team_from_row(r: &Row) -> rusqlite::Result<Team> {
+    Ok(Team {
+        id: r.get(0)?,
+        name: r.get(1)?,
+        roster: Roster(
+            BattleTag::new(r.get::<_, String>(2)?),
+            BattleTag::new(r.get::<_, String>(3)?),
+            BattleTag::new(r.get::<_, String>(4)?),
+        ),
+    })
+}
+
+fn roster_ids<C: Deref<Target = Connection>>(
+    conn: C,
+    roster: &Roster,
+) -> Result<[i64; 3], MatchDbError> {
+    let Roster(p1, p2, p3) = roster;
+    Ok([
+        get_or_insert_battletag_id(conn.deref(), p1)?,
+        get_or_insert_battletag_id(conn.deref(), p2)?,
+        get_or_insert_battletag_id(conn.deref(), p3)?,
+    ])
+}
+
+fn name_conflict(e: RusqliteError, name: &str) -> MatchDbError {
+    if let RusqliteError::SqliteFailure(ffierr, _) = e {
+        if let ErrorCode::ConstraintViolation = ffierr.code {
+            return MatchDbError::TeamAlreadyExists(name.to_string());
+        }
+    }
+    e.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::round;
+    use overwatch::overwatch_3v3::{Match, RoundOutcome};
+    use overwatch::Hero;
+
+    fn trio() -> Roster {
+        Roster::new("player1", "player2", "player3")
+    }
+
+    #[test]
+    fn team_crud() {
+        let mut db = MatchDb::default();
+        let team = db.create_team("regulars", &trio()).unwrap();
+        assert_eq!(db.get_team(team.id).unwrap(), team);
+
+        let other = db
+            .create_team("guests", &Roster::new("player1", "guest1", "guest2"))
+            .unwrap();
+        assert_eq!(db.list_teams().unwrap(), vec![other.clone(), team.clone()]);
+
+        let renamed = db
+            .update_team(team.id, "weeknights", &Roster::new("player3", "player2", "player1"))
+            .unwrap();
+        assert_eq!(db.get_team(team.id).unwrap(), renamed);
+
+        db.delete_team(other.id).unwrap();
+        assert_eq!(db.list_teams().unwrap(), vec![renamed]);
+        match db.get_team(other.id) {
+            Err(MatchDbError::TeamDoesNotExist(id)) => assert_eq!(id, other.id),
+            r => panic!("expected missing team error: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn team_names_are_unique() {
+        let mut db = MatchDb::default();
+        let team = db.create_team("regulars", &trio()).unwrap();
+        let other = db.create_team("guests", &trio()).unwrap();
+        match db.create_team("regulars", &trio()) {
+            Err(MatchDbError::TeamAlreadyExists(name)) => assert_eq!(name, "regulars"),
+            r => panic!("expected duplicate team error: {:?}", r),
+        }
+        match db.update_team(other.id, &team.name, &trio()) {
+            Err(MatchDbError::TeamAlreadyExists(_)) => {},
+            r => panic!("expected duplicate team error: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn matches_remember_their_team() {
+        let mut db = MatchDb::default();
+        let team = db.create_team("regulars", &trio()).unwrap();
+        let mut m = Match::default();
+        m.insert_round(round([Hero::Ana, Hero::Mei, Hero::Sigma], RoundOutcome::Win))
+            .unwrap();
+        let with_team = db.record_team_match(&m, Some(team.id)).unwrap();
+        let without = db.record_match(&m).unwrap();
+        assert_eq!(db.load_match(with_team).unwrap().team_id, Some(team.id));
+        assert_eq!(db.load_match(without).unwrap().team_id, None);
+
+        db.delete_team(team.id).unwrap();
+        assert_eq!(db.load_match(with_team).unwrap().team_id, None);
+    }
+}
@@ -0,0 +1,31 @@
+//! Matches for the tests to record.
+
+use crate::MatchDb;
+use overwatch::overwatch_3v3::{GameRules, Match, Round, RoundOutcome};
+use overwatch::{BattleTag, Hero, Map};
+
+/// A round of `player1`, `player2` and `player3` on `heros`.
+pub fn round(heros: [Hero; 3], outcome: RoundOutcome) -> Round {
+    round_of(["player1", "player2", "player3"], heros, outcome)
+}
+
+/// A round of `players` on `heros`, both indexed by `Player::index`.
+pub fn round_of(players: [&str; 3], heros: [Hero; 3], outcome: RoundOutcome) -> Round {
+    Round {
+        player1: (BattleTag::new(players[0]), heros[0]),
+        player2: (BattleTag::new(players[1]), heros[1]),
+        player3: (BattleTag::new(players[2]), heros[2]),
+        outcome,
+        enemy: None,
+    }
+}
+
+/// Record an elimination match of `rounds` on `map`, returning its id.
+pub fn record(db: &mut MatchDb, map: Option<Map>, rounds: &[Round]) -> i64 {
+    let mut m = Match::new(GameRules::elimination());
+    m.set_map(map);
+    for r in rounds {
+        m.insert_round(r.clone()).unwrap();
+    }
+    db.record_match(&m).unwrap()
+}
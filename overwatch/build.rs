@@ -0,0 +1,64 @@
+//! Generate the `Hero` enum from the bundled hero definitions.
+//!
+//! Only the ids are baked in at compile time; everything else about a hero is read
+//! from the same file at runtime, see `hero::registry`.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const HERO_DATA: &str = "data/heroes.toml";
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", HERO_DATA);
+
+    let data = fs::read_to_string(HERO_DATA).expect("could not read hero data");
+    let data: toml::Value = data.parse().expect("hero data is not valid toml");
+    let ids = data
+        .get("hero")
+        .and_then(|h| h.as_array())
+        .expect("hero data must contain [[hero]] entries")
+        .iter()
+        .map(|h| {
+            h.get("id")
+                .and_then(|id| id.as_str())
+                .expect("every hero needs an id")
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+    writeln!(out, "/// A playable hero, generated from `{}`.", HERO_DATA).unwrap();
+    writeln!(
+        out,
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, IntoEnumIterator)]"
+    )
+    .unwrap();
+    writeln!(out, "pub enum Hero {{").unwrap();
+    for id in &ids {
+        writeln!(out, "    {},", variant_name(id)).unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "const HERO_IDS: [&str; {}] = [", ids.len()).unwrap();
+    for id in &ids {
+        writeln!(out, "    {:?},", id).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("heroes.rs");
+    fs::write(dest, out).expect("could not write generated heroes");
+}
+
+/// `wrecking-ball` -> `WreckingBall`, `soldier-76` -> `Soldier76`
+fn variant_name(id: &str) -> String {
+    id.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
@@ -0,0 +1,204 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// Characters allowed in the name part of a battletag.
+const NAME_LENGTH: RangeInclusive<usize> = 3..=12;
+/// Digits allowed in the number after the `#`.
+const DISCRIMINATOR_LENGTH: RangeInclusive<usize> = 4..=5;
+
+#[derive(Debug, Fail, Clone, PartialEq, Eq)]
+pub enum ParseBattleTagError {
+    #[fail(display = "battletag is empty")]
+    Empty,
+    #[fail(display = "battletag name '{}' must be 3 to 12 characters long", _0)]
+    NameLength(String),
+    #[fail(display = "battletag name '{}' must start with a letter", _0)]
+    NameStartsWithDigit(String),
+    #[fail(display = "battletag '{}' may only contain letters and digits, not '{}'", _0, _1)]
+    InvalidCharacter(String, char),
+    #[fail(display = "battletag number '{}' must be 4 or 5 digits", _0)]
+    InvalidDiscriminator(String),
+}
+
+/// A player's battletag, shown the way it was typed but compared without regard to case.
+#[derive(Debug, Clone)]
+pub struct BattleTag {
+    display: String,
+    key: String,
+}
+
+impl<S: AsRef<str>> From<S> for BattleTag {
+    fn from(s: S) -> BattleTag {
+        BattleTag {
+            display: s.as_ref().to_string(),
+            key: s.as_ref().to_lowercase(),
+        }
+    }
+}
+
+impl PartialEq for BattleTag {
+    fn eq(&self, other: &BattleTag) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for BattleTag {}
+
+impl Hash for BattleTag {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state)
+    }
+}
+
+impl PartialOrd for BattleTag {
+    fn partial_cmp(&self, other: &BattleTag) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BattleTag {
+    fn cmp(&self, other: &BattleTag) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+impl BattleTag {
+    pub fn new<S: Into<BattleTag>>(battletag: S) -> BattleTag {
+        battletag.into()
+    }
+
+    /// Parse a battletag typed in by a user, as `Name` or `Name#1234`.
+    ///
+    /// Unlike `new` this follows Blizzard's rules: the name is 3 to 12 letters or
+    /// digits and doesn't start with a digit, and the number after the `#` is 4 or 5
+    /// digits. Surrounding whitespace is ignored.
+    pub fn parse(s: &str) -> Result<BattleTag, ParseBattleTagError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseBattleTagError::Empty);
+        }
+        let (name, discriminator) = match s.find('#') {
+            Some(idx) => (&s[..idx], Some(&s[idx + 1..])),
+            None => (s, None),
+        };
+
+        if let Some(c) = name.chars().find(|c| !c.is_alphanumeric()) {
+            return Err(ParseBattleTagError::InvalidCharacter(s.to_string(), c));
+        }
+        if !NAME_LENGTH.contains(&name.chars().count()) {
+            return Err(ParseBattleTagError::NameLength(name.to_string()));
+        }
+        if name.starts_with(|c: char| c.is_numeric()) {
+            return Err(ParseBattleTagError::NameStartsWithDigit(name.to_string()));
+        }
+        if let Some(discriminator) = discriminator {
+            let digits = discriminator.chars().all(|c| c.is_ascii_digit());
+            if !digits || !DISCRIMINATOR_LENGTH.contains(&discriminator.len()) {
+                return Err(ParseBattleTagError::InvalidDiscriminator(
+                    discriminator.to_string(),
+                ));
+            }
+        }
+        Ok(BattleTag::new(s))
+    }
+
+    /// The battletag as the player spells it.
+    pub fn as_str(&self) -> &str {
+        self.display.as_str()
+    }
+
+    /// The lowercase form that identifies the player, for lookups and comparisons.
+    pub fn key(&self) -> &str {
+        self.key.as_str()
+    }
+
+    pub fn into_inner(self) -> String {
+        self.display
+    }
+
+    /// The part before the `#`, or the whole battletag if it has no number.
+    pub fn name(&self) -> &str {
+        match self.display.find('#') {
+            Some(idx) => &self.display[..idx],
+            None => &self.display,
+        }
+    }
+
+    /// The number after the `#`, kept as text so leading zeros survive.
+    pub fn discriminator(&self) -> Option<&str> {
+        self.display.find('#').map(|idx| &self.display[idx + 1..])
+    }
+}
+
+impl FromStr for BattleTag {
+    type Err = ParseBattleTagError;
+
+    fn from_str(s: &str) -> Result<BattleTag, ParseBattleTagError> {
+        BattleTag::parse(s)
+    }
+}
+
+impl fmt::Display for BattleTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_name_and_discriminator() {
+        let bt = BattleTag::parse(" Sombra#12345 ").unwrap();
+        assert_eq!(bt.name(), "Sombra");
+        assert_eq!(bt.discriminator(), Some("12345"));
+
+        let bt = BattleTag::parse("Zoë2").unwrap();
+        assert_eq!(bt.name(), "Zoë2");
+        assert_eq!(bt.discriminator(), None);
+        assert_eq!("Zoë2".parse::<BattleTag>().unwrap(), bt);
+    }
+
+    #[test]
+    fn display_keeps_case_identity_ignores_it() {
+        use std::collections::HashSet;
+        let typed = BattleTag::new("PlayerOne#1234");
+        let lower = BattleTag::new("playerone#1234");
+        assert_eq!(typed.to_string(), "PlayerOne#1234");
+        assert_eq!(typed.key(), "playerone#1234");
+        assert_eq!(typed, lower);
+        assert_eq!(typed.cmp(&lower), Ordering::Equal);
+        let set = vec![typed, lower].into_iter().collect::<HashSet<_>>();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn reject_invalid_battletags() {
+        use ParseBattleTagError::*;
+        let cases = [
+            ("", Empty),
+            ("   ", Empty),
+            ("ab", NameLength("ab".into())),
+            ("abcdefghijklm#1234", NameLength("abcdefghijklm".into())),
+            ("1ana", NameStartsWithDigit("1ana".into())),
+            ("ana mei", InvalidCharacter("ana mei".into(), ' ')),
+            ("player-new", InvalidCharacter("player-new".into(), '-')),
+            ("ana#", InvalidDiscriminator("".into())),
+            ("ana#123", InvalidDiscriminator("123".into())),
+            ("ana#12a4", InvalidDiscriminator("12a4".into())),
+            ("ana#1234#5678", InvalidDiscriminator("1234#5678".into())),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(
+                BattleTag::parse(input).as_ref(),
+                Err(expected),
+                "parsing '{}'",
+                input
+            );
+        }
+    }
+}
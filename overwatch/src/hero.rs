@@ -1,10 +1,16 @@
 use enum_iterator::IntoEnumIterator;
-use failure::_core::str::FromStr;
 use lazy_static::lazy_static;
+use serde::Deserialize;
 use std::collections::HashSet;
 use std::fmt;
+use std::str::FromStr;
+
+pub use self::registry::{HeroDataError, HeroDef, RoleEra, HERO_DATA_ENV};
+
+mod registry;
+
+include!(concat!(env!("OUT_DIR"), "/heroes.rs"));
 
-const NUM_HEROS: usize = 31;
 lazy_static! {
     pub static ref HEROPOOL: HashSet<Hero> = Hero::into_enum_iter().collect();
 }
@@ -13,7 +19,10 @@ lazy_static! {
 #[fail(display = "could not parse hero from '{}'", _0)]
 pub struct ParseHeroError(String);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, IntoEnumIterator)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, IntoEnumIterator, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
 pub enum Role {
     Dps,
     Tank,
@@ -26,45 +35,9 @@ impl Role {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, IntoEnumIterator)]
-pub enum Hero {
-    Ana,
-    Ashe,
-    Baptiste,
-    Bastion,
-    Brigitte,
-    Dva,
-    Doomfist,
-    Echo,
-    Genji,
-    Hanzo,
-    Junkrat,
-    Lucio,
-    Mccree,
-    Mei,
-    Mercy,
-    Moira,
-    Orisa,
-    Pharah,
-    Reaper,
-    Reinhardt,
-    Roadhog,
-    Sigma,
-    Soldier76,
-    Sombra,
-    Symmetra,
-    Torbjorn,
-    Tracer,
-    Widowmaker,
-    Winston,
-    WreckingBall,
-    Zarya,
-    Zenyatta,
-}
-
 impl fmt::Display for Hero {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
+        write!(f, "{}", self.name())
     }
 }
 
@@ -75,135 +48,108 @@ impl Hero {
         Self::into_enum_iter()
     }
 
+    /// Everything we know about this hero, from the hero data file.
+    pub fn def(self) -> &'static HeroDef {
+        registry::registry().get(self)
+    }
+
+    /// The hero's role today.
     pub fn role(self) -> Role {
-        match self {
-            Hero::Ana => Role::Support,
-            Hero::Ashe => Role::Dps,
-            Hero::Baptiste => Role::Support,
-            Hero::Bastion => Role::Dps,
-            Hero::Brigitte => Role::Support,
-            Hero::Dva => Role::Tank,
-            Hero::Doomfist => Role::Dps,
-            Hero::Echo => Role::Dps,
-            Hero::Genji => Role::Dps,
-            Hero::Hanzo => Role::Dps,
-            Hero::Junkrat => Role::Dps,
-            Hero::Lucio => Role::Support,
-            Hero::Mccree => Role::Dps,
-            Hero::Mei => Role::Dps,
-            Hero::Mercy => Role::Support,
-            Hero::Moira => Role::Support,
-            Hero::Orisa => Role::Tank,
-            Hero::Pharah => Role::Dps,
-            Hero::Reaper => Role::Dps,
-            Hero::Reinhardt => Role::Tank,
-            Hero::Roadhog => Role::Tank,
-            Hero::Sigma => Role::Tank,
-            Hero::Soldier76 => Role::Dps,
-            Hero::Sombra => Role::Dps,
-            Hero::Symmetra => Role::Dps,
-            Hero::Torbjorn => Role::Dps,
-            Hero::Tracer => Role::Dps,
-            Hero::Widowmaker => Role::Dps,
-            Hero::Winston => Role::Tank,
-            Hero::WreckingBall => Role::Tank,
-            Hero::Zarya => Role::Tank,
-            Hero::Zenyatta => Role::Support,
-        }
+        self.def().role
     }
 
-    /// This is the name used for loading assets
-    /// and for database entries. Never change these, as
-    /// they are "string-ly" typed.
+    /// The hero's role on `date`, for looking back at old matches.
+    ///
+    /// `date` is a `YYYY-MM-DD` date or a timestamp starting with one, like a match's.
+    /// Those sort as text in date order, so no parsing is needed.
+    pub fn role_at(self, date: &str) -> Role {
+        self.def()
+            .former_roles
+            .iter()
+            .find(|era| date < era.until.as_str())
+            .map(|era| era.role)
+            .unwrap_or_else(|| self.role())
+    }
+
+    /// The name to show to people, e.g. "Soldier: 76".
+    pub fn name(self) -> &'static str {
+        &self.def().name
+    }
+
+    /// The name used for loading assets, such as portraits.
+    pub fn asset_name(self) -> &'static str {
+        &self.def().asset
+    }
+
+    /// This is the name used for database entries. Databases are "string-ly" typed, so
+    /// when a hero is renamed their old id goes in `former_ids`, which still parses.
     pub fn blizzard_name(self) -> &'static str {
-        match self {
-            Hero::Ana => "ana",
-            Hero::Ashe => "ashe",
-            Hero::Baptiste => "baptiste",
-            Hero::Bastion => "bastion",
-            Hero::Brigitte => "brigitte",
-            Hero::Dva => "dva",
-            Hero::Doomfist => "doomfist",
-            Hero::Echo => "echo",
-            Hero::Genji => "genji",
-            Hero::Hanzo => "hanzo",
-            Hero::Junkrat => "junkrat",
-            Hero::Lucio => "lucio",
-            Hero::Mccree => "mccree",
-            Hero::Mei => "mei",
-            Hero::Mercy => "mercy",
-            Hero::Moira => "moira",
-            Hero::Orisa => "orisa",
-            Hero::Pharah => "pharah",
-            Hero::Reaper => "reaper",
-            Hero::Reinhardt => "reinhardt",
-            Hero::Roadhog => "roadhog",
-            Hero::Sigma => "sigma",
-            Hero::Soldier76 => "soldier-76",
-            Hero::Sombra => "sombra",
-            Hero::Symmetra => "symmetra",
-            Hero::Torbjorn => "torbjorn",
-            Hero::Tracer => "tracer",
-            Hero::Widowmaker => "widowmaker",
-            Hero::Winston => "winston",
-            Hero::WreckingBall => "wrecking-ball",
-            Hero::Zarya => "zarya",
-            Hero::Zenyatta => "zenyatta",
-        }
+        HERO_IDS[self as usize]
+    }
+
+    /// Ids this hero was stored under before being renamed, oldest first.
+    pub fn former_ids(self) -> &'static [String] {
+        &self.def().former_ids
     }
 }
 
 impl FromStr for Hero {
     type Err = ParseHeroError;
 
+    /// Accepts a hero's id, or any of its aliases, ignoring case.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "ana" => Hero::Ana,
-            "ashe" => Hero::Ashe,
-            "baptiste" => Hero::Baptiste,
-            "bastion" => Hero::Bastion,
-            "brigitte" => Hero::Brigitte,
-            "dva" => Hero::Dva,
-            "doomfist" => Hero::Doomfist,
-            "echo" => Hero::Echo,
-            "genji" => Hero::Genji,
-            "hanzo" => Hero::Hanzo,
-            "junkrat" => Hero::Junkrat,
-            "lucio" => Hero::Lucio,
-            "mccree" => Hero::Mccree,
-            "mei" => Hero::Mei,
-            "mercy" => Hero::Mercy,
-            "moira" => Hero::Moira,
-            "orisa" => Hero::Orisa,
-            "pharah" => Hero::Pharah,
-            "reaper" => Hero::Reaper,
-            "reinhardt" => Hero::Reinhardt,
-            "roadhog" => Hero::Roadhog,
-            "sigma" => Hero::Sigma,
-            "soldier-76" => Hero::Soldier76,
-            "sombra" => Hero::Sombra,
-            "symmetra" => Hero::Symmetra,
-            "torbjorn" => Hero::Torbjorn,
-            "tracer" => Hero::Tracer,
-            "widowmaker" => Hero::Widowmaker,
-            "winston" => Hero::Winston,
-            "wrecking-ball" => Hero::WreckingBall,
-            "zarya" => Hero::Zarya,
-            "zenyatta" => Hero::Zenyatta,
-            s => return Err(ParseHeroError(s.into())),
-        })
+        registry::registry()
+            .lookup(s)
+            .ok_or_else(|| ParseHeroError(s.into()))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Hero, ParseHeroError, HEROPOOL, NUM_HEROS};
+    use super::{Hero, ParseHeroError, Role, HEROPOOL, HERO_IDS};
     use std::str::FromStr;
 
     #[test]
     fn hero_pool() {
         assert!(HEROPOOL.contains(&Hero::Mercy));
-        assert_eq!(HEROPOOL.len(), NUM_HEROS);
+        assert_eq!(HEROPOOL.len(), HERO_IDS.len());
+    }
+
+    #[test]
+    fn hero_data() {
+        assert_eq!(Hero::Soldier76.blizzard_name(), "soldier-76");
+        assert_eq!(Hero::Soldier76.name(), "Soldier: 76");
+        assert_eq!(Hero::WreckingBall.asset_name(), "wrecking-ball");
+        assert_eq!(Hero::Dva.role(), Role::Tank);
+        assert!(Role::Support.heros().any(|h| h == Hero::Zenyatta));
+        assert!(!Role::Support.heros().any(|h| h == Hero::Zarya));
+    }
+
+    #[test]
+    fn role_by_date() {
+        assert_eq!(Hero::Doomfist.role_at("2019-11-02 20:15:00"), Role::Dps);
+        assert_eq!(Hero::Doomfist.role_at("2022-10-04"), Role::Tank);
+        assert_eq!(Hero::Doomfist.role(), Role::Tank);
+        assert_eq!(Hero::Symmetra.role_at("2017-01-01"), Role::Support);
+        assert_eq!(Hero::Symmetra.role_at("2019-11-02"), Role::Dps);
+        assert_eq!(Hero::Mercy.role_at("2016-05-24"), Role::Support);
+    }
+
+    #[test]
+    fn parse_hero_alias() {
+        assert_eq!(Hero::from_str("d.va").unwrap(), Hero::Dva);
+        assert_eq!(Hero::from_str("Torbjörn").unwrap(), Hero::Torbjorn);
+        assert_eq!(Hero::from_str("MERCY").unwrap(), Hero::Mercy);
+    }
+
+    #[test]
+    fn parse_renamed_hero() {
+        assert_eq!(Hero::from_str("mccree").unwrap(), Hero::Cassidy);
+        assert_eq!(Hero::from_str("McCree").unwrap(), Hero::Cassidy);
+        assert_eq!(Hero::Cassidy.blizzard_name(), "cassidy");
+        assert_eq!(Hero::Cassidy.name(), "Cassidy");
+        assert_eq!(Hero::Cassidy.asset_name(), "cassidy");
+        assert_eq!(Hero::Cassidy.former_ids(), ["mccree"]);
     }
 
     #[test]
@@ -0,0 +1,286 @@
+use super::{Hero, Role, HERO_IDS};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::{env, fs, io};
+
+const BUNDLED_HERO_DATA: &str = include_str!("../../data/heroes.toml");
+
+/// Point this at a toml file of `[[hero]]` entries to override the bundled hero data.
+pub const HERO_DATA_ENV: &str = "OW3V3_HERO_DATA";
+
+lazy_static! {
+    static ref REGISTRY: HeroRegistry = load();
+}
+
+pub(crate) fn registry() -> &'static HeroRegistry {
+    &REGISTRY
+}
+
+fn load() -> HeroRegistry {
+    let mut registry = HeroRegistry::bundled().expect("bundled hero data is invalid");
+    if let Some(path) = env::var_os(HERO_DATA_ENV) {
+        let overrides = fs::read_to_string(&path)
+            .map_err(HeroDataError::Io)
+            .and_then(|data| registry.apply_overrides(&data));
+        match overrides {
+            Ok(()) => info!("loaded hero data overrides from {:?}", path),
+            Err(e) => error!("ignoring hero data overrides from {:?}: {}", path, e),
+        }
+    }
+    registry
+}
+
+#[derive(Debug, Fail)]
+pub enum HeroDataError {
+    #[fail(display = "could not read hero data: {}", _0)]
+    Io(#[cause] io::Error),
+    #[fail(display = "invalid hero data: {}", _0)]
+    Parse(#[cause] toml::de::Error),
+    #[fail(display = "hero data is missing '{}'", _0)]
+    MissingHero(String),
+    #[fail(display = "hero data has an unknown hero '{}'", _0)]
+    UnknownHero(String),
+}
+
+impl From<toml::de::Error> for HeroDataError {
+    fn from(e: toml::de::Error) -> HeroDataError {
+        HeroDataError::Parse(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct HeroDef {
+    pub id: String,
+    pub name: String,
+    /// The hero's role today, see `former_roles` for what it was before.
+    pub role: Role,
+    #[serde(default)]
+    pub former_roles: Vec<RoleEra>,
+    pub asset: String,
+    /// Ids the hero had before Blizzard renamed them, which may still be in a database.
+    #[serde(default)]
+    pub former_ids: Vec<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub patch: String,
+}
+
+/// A role a hero had until Blizzard moved them to another.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RoleEra {
+    pub role: Role,
+    /// The day the hero stopped being this role, as `YYYY-MM-DD`.
+    pub until: String,
+}
+
+/// An entry in an override file, where everything but the id is optional.
+///
+/// The id may be a former id, so override files written before a rename still apply.
+#[derive(Debug, Deserialize)]
+struct HeroOverride {
+    id: String,
+    name: Option<String>,
+    role: Option<Role>,
+    former_roles: Option<Vec<RoleEra>>,
+    asset: Option<String>,
+    aliases: Option<Vec<String>>,
+    patch: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HeroFile<T> {
+    hero: Vec<T>,
+}
+
+#[derive(Debug)]
+pub(crate) struct HeroRegistry {
+    /// Indexed by `Hero as usize`
+    defs: Vec<HeroDef>,
+    lookup: HashMap<String, Hero>,
+}
+
+impl HeroRegistry {
+    fn bundled() -> Result<HeroRegistry, HeroDataError> {
+        let file: HeroFile<HeroDef> = toml::from_str(BUNDLED_HERO_DATA)?;
+        let mut by_id = file
+            .hero
+            .into_iter()
+            .map(|def| (def.id.clone(), def))
+            .collect::<HashMap<_, _>>();
+        let defs = HERO_IDS
+            .iter()
+            .map(|id| {
+                by_id
+                    .remove(*id)
+                    .ok_or_else(|| HeroDataError::MissingHero(id.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(id) = by_id.keys().next() {
+            return Err(HeroDataError::UnknownHero(id.clone()));
+        }
+        let mut registry = HeroRegistry {
+            defs,
+            lookup: HashMap::new(),
+        };
+        registry.build_lookup();
+        Ok(registry)
+    }
+
+    /// Apply an override file on top of the current definitions.
+    ///
+    /// Heroes can only be changed, not added, since every hero needs a `Hero` variant.
+    /// Nothing is applied unless the whole file is valid.
+    fn apply_overrides(&mut self, data: &str) -> Result<(), HeroDataError> {
+        let file: HeroFile<HeroOverride> = toml::from_str(data)?;
+        let overrides = file
+            .hero
+            .into_iter()
+            .map(|o| match self.position(&o.id) {
+                Some(idx) => Ok((idx, o)),
+                None => Err(HeroDataError::UnknownHero(o.id)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (idx, o) in overrides {
+            let def = &mut self.defs[idx];
+            if let Some(name) = o.name {
+                def.name = name;
+            }
+            if let Some(role) = o.role {
+                def.role = role;
+            }
+            if let Some(former_roles) = o.former_roles {
+                def.former_roles = former_roles;
+            }
+            if let Some(asset) = o.asset {
+                def.asset = asset;
+            }
+            if let Some(aliases) = o.aliases {
+                def.aliases = aliases;
+            }
+            if let Some(patch) = o.patch {
+                def.patch = patch;
+            }
+        }
+        self.build_lookup();
+        Ok(())
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        self.defs
+            .iter()
+            .position(|def| def.id == id || def.former_ids.iter().any(|f| f == id))
+    }
+
+    fn build_lookup(&mut self) {
+        self.lookup = Hero::iter()
+            .flat_map(|hero| {
+                let def = &self.defs[hero as usize];
+                std::iter::once(&def.id)
+                    .chain(def.former_ids.iter())
+                    .chain(def.aliases.iter())
+                    .map(move |name| (name.to_lowercase(), hero))
+            })
+            .collect();
+    }
+
+    pub(crate) fn get(&self, hero: Hero) -> &HeroDef {
+        &self.defs[hero as usize]
+    }
+
+    pub(crate) fn lookup(&self, name: &str) -> Option<Hero> {
+        self.lookup.get(&name.to_lowercase()).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HeroDataError, HeroRegistry};
+    use crate::{Hero, Role};
+
+    #[test]
+    fn bundled_matches_generated_heros() {
+        let registry = HeroRegistry::bundled().unwrap();
+        for hero in Hero::iter() {
+            assert_eq!(registry.get(hero).id, hero.blizzard_name());
+        }
+    }
+
+    #[test]
+    fn bundled_former_roles_are_dated_in_order() {
+        let registry = HeroRegistry::bundled().unwrap();
+        for hero in Hero::iter() {
+            let eras = &registry.get(hero).former_roles;
+            for era in eras {
+                let date = era.until.split('-').map(str::len).collect::<Vec<_>>();
+                assert_eq!(date, [4, 2, 2], "{} has a bad date {}", hero, era.until);
+            }
+            assert!(
+                eras.windows(2).all(|w| w[0].until < w[1].until),
+                "{} former roles are out of order",
+                hero
+            );
+        }
+    }
+
+    #[test]
+    fn override_hero() {
+        let mut registry = HeroRegistry::bundled().unwrap();
+        registry
+            .apply_overrides(
+                r#"
+                [[hero]]
+                id = "doomfist"
+                role = "dps"
+                former_roles = []
+                aliases = ["doom"]
+                "#,
+            )
+            .unwrap();
+
+        let doomfist = registry.get(Hero::Doomfist);
+        assert_eq!(doomfist.role, Role::Dps);
+        assert!(doomfist.former_roles.is_empty());
+        assert_eq!(doomfist.name, "Doomfist");
+        assert_eq!(registry.lookup("doom"), Some(Hero::Doomfist));
+        assert_eq!(registry.lookup("doomfist"), Some(Hero::Doomfist));
+    }
+
+    #[test]
+    fn override_renamed_hero_by_former_id() {
+        let mut registry = HeroRegistry::bundled().unwrap();
+        registry
+            .apply_overrides(
+                r#"
+                [[hero]]
+                id = "mccree"
+                aliases = ["jesse"]
+                "#,
+            )
+            .unwrap();
+        assert_eq!(registry.lookup("jesse"), Some(Hero::Cassidy));
+        assert_eq!(registry.lookup("mccree"), Some(Hero::Cassidy));
+    }
+
+    #[test]
+    fn override_unknown_hero() {
+        let mut registry = HeroRegistry::bundled().unwrap();
+        let result = registry.apply_overrides(
+            r#"
+            [[hero]]
+            id = "mercy"
+            role = "dps"
+
+            [[hero]]
+            id = "pharmacy"
+            role = "support"
+            "#,
+        );
+        match result {
+            Err(HeroDataError::UnknownHero(id)) => assert_eq!(id, "pharmacy"),
+            r => panic!("expected unknown hero error: {:?}", r),
+        }
+        assert_eq!(registry.get(Hero::Mercy).role, Role::Support);
+    }
+}
@@ -1,44 +1,20 @@
+// `failure_derive` emits its impls inside an anonymous const
+#![allow(non_local_definitions)]
+
 #[macro_use]
 extern crate failure;
+#[macro_use]
+extern crate log;
 
+mod battletag;
 mod hero;
-
-pub use battletag::BattleTag;
-pub use hero::{Hero, HeroPool, Role, HEROPOOL};
-
-mod battletag {
-    use std::fmt;
-
-    #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
-    pub struct BattleTag {
-        inner: String,
-    }
-
-    impl<S: AsRef<str>> From<S> for BattleTag {
-        fn from(s: S) -> BattleTag {
-            BattleTag {
-                inner: s.as_ref().to_lowercase(),
-            }
-        }
-    }
-
-    impl BattleTag {
-        pub fn new<S: Into<BattleTag>>(battletag: S) -> BattleTag {
-            battletag.into()
-        }
-        pub fn as_str(&self) -> &str {
-            self.inner.as_str()
-        }
-        pub fn into_inner(self) -> String {
-            self.inner
-        }
-    }
-
-    impl fmt::Display for BattleTag {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "{}", self.inner)
-        }
-    }
-}
+mod map;
+
+pub use battletag::{BattleTag, ParseBattleTagError};
+pub use hero::{
+    Hero, HeroDataError, HeroDef, HeroPool, ParseHeroError, Role, RoleEra, HEROPOOL,
+    HERO_DATA_ENV,
+};
+pub use map::{Map, ParseMapError};
 
 pub mod overwatch_3v3;
@@ -0,0 +1,120 @@
+use enum_iterator::IntoEnumIterator;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Fail)]
+#[fail(display = "could not parse map from '{}'", _0)]
+pub struct ParseMapError(String);
+
+/// An arena in the Elimination map pool.
+///
+/// Control maps are listed per stage, since each stage is its own arena.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, IntoEnumIterator)]
+pub enum Map {
+    BlackForest,
+    Castillo,
+    ChateauGuillard,
+    EcopointAntarctica,
+    IliosLighthouse,
+    IliosRuins,
+    IliosWell,
+    LijiangControlCenter,
+    LijiangGarden,
+    LijiangNightMarket,
+    Necropolis,
+    NepalSanctum,
+    NepalShrine,
+    NepalVillage,
+    OasisCityCenter,
+    OasisGardens,
+    OasisUniversity,
+    Petra,
+}
+
+impl Map {
+    #[inline]
+    pub fn iter() -> MapEnumIterator {
+        Map::into_enum_iter()
+    }
+
+    /// The name used for database entries.
+    pub fn key(self) -> &'static str {
+        match self {
+            Map::BlackForest => "black-forest",
+            Map::Castillo => "castillo",
+            Map::ChateauGuillard => "chateau-guillard",
+            Map::EcopointAntarctica => "ecopoint-antarctica",
+            Map::IliosLighthouse => "ilios-lighthouse",
+            Map::IliosRuins => "ilios-ruins",
+            Map::IliosWell => "ilios-well",
+            Map::LijiangControlCenter => "lijiang-control-center",
+            Map::LijiangGarden => "lijiang-garden",
+            Map::LijiangNightMarket => "lijiang-night-market",
+            Map::Necropolis => "necropolis",
+            Map::NepalSanctum => "nepal-sanctum",
+            Map::NepalShrine => "nepal-shrine",
+            Map::NepalVillage => "nepal-village",
+            Map::OasisCityCenter => "oasis-city-center",
+            Map::OasisGardens => "oasis-gardens",
+            Map::OasisUniversity => "oasis-university",
+            Map::Petra => "petra",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Map::BlackForest => "Black Forest",
+            Map::Castillo => "Castillo",
+            Map::ChateauGuillard => "Château Guillard",
+            Map::EcopointAntarctica => "Ecopoint: Antarctica",
+            Map::IliosLighthouse => "Ilios Lighthouse",
+            Map::IliosRuins => "Ilios Ruins",
+            Map::IliosWell => "Ilios Well",
+            Map::LijiangControlCenter => "Lijiang Control Center",
+            Map::LijiangGarden => "Lijiang Garden",
+            Map::LijiangNightMarket => "Lijiang Night Market",
+            Map::Necropolis => "Necropolis",
+            Map::NepalSanctum => "Nepal Sanctum",
+            Map::NepalShrine => "Nepal Shrine",
+            Map::NepalVillage => "Nepal Village",
+            Map::OasisCityCenter => "Oasis City Center",
+            Map::OasisGardens => "Oasis Gardens",
+            Map::OasisUniversity => "Oasis University",
+            Map::Petra => "Petra",
+        }
+    }
+}
+
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Map {
+    type Err = ParseMapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Map::iter()
+            .find(|m| m.key() == s)
+            .ok_or_else(|| ParseMapError(s.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Map;
+    use std::str::FromStr;
+
+    #[test]
+    fn parse_all_maps() {
+        for map in Map::iter() {
+            assert_eq!(Map::from_str(map.key()).unwrap(), map);
+        }
+    }
+
+    #[test]
+    fn parse_map_failure() {
+        assert!(Map::from_str("hanamura").is_err());
+    }
+}
@@ -1,14 +1,24 @@
-use crate::{BattleTag, Hero, HeroPool};
+use crate::{BattleTag, Hero, HeroPool, Map, HEROPOOL};
 use std::collections::HashSet;
+use std::mem;
 
+pub use self::planner::{plan, Plan, PlanStep, DEFAULT_BREADTH};
 pub use self::player::Player;
+pub use self::recommend::{recommend, CompScorer, NeutralScorer, Recommender, Suggestion};
 pub use self::roster::Roster;
-pub use self::round::Round;
+pub use self::round::{ParseOutcomeError, Round, RoundOutcome, Team};
+pub use self::rules::{GameRules, HeroLock, MatchOutcome, ParseRuleError, TieBreak};
+
+mod planner;
 
 mod player;
 
+mod recommend;
+
 mod round;
 
+mod rules;
+
 mod roster {
     use crate::BattleTag;
 
@@ -40,16 +50,28 @@ pub enum MatchHistoryError {
     MissingOutcome,
     #[fail(display = "Duplicate hero in match: {}", _0)]
     DuplicateHero(Hero),
-    #[fail(display = "Matches can not last longer than 5 rounds")]
-    TooManyRounds,
+    #[fail(display = "Duplicate enemy hero in match: {}", _0)]
+    DuplicateEnemyHero(Hero),
+    #[fail(display = "Incomplete enemy composition, no hero for enemy {}", _0)]
+    MissingEnemyHero(Player),
+    #[fail(display = "The match is already over")]
+    MatchOver,
+    #[fail(display = "No round at index {} in match", _0)]
+    NoSuchRound(usize),
+    #[fail(display = "Round at index {} would become invalid: {}", _0, _1)]
+    InvalidRound(usize, Box<MatchHistoryError>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CompBuilder {
     player1: (BattleTag, Option<Hero>),
     player2: (BattleTag, Option<Hero>),
     player3: (BattleTag, Option<Hero>),
-    win: Option<bool>,
+    outcome: Option<RoundOutcome>,
+    /// Either empty, or all three heroes once the enemy comp is complete.
+    enemy: [Option<Hero>; 3],
+    /// Heroes each player is willing to play, `None` for the whole roster.
+    pools: [Option<HeroPool>; 3],
 }
 
 impl From<Round> for CompBuilder {
@@ -58,14 +80,20 @@ impl From<Round> for CompBuilder {
             player1: (bt1, h1),
             player2: (bt2, h2),
             player3: (bt3, h3),
-            win: win,
+            outcome,
+            enemy,
         } = r;
 
         CompBuilder {
             player1: (bt1, Some(h1)),
             player2: (bt2, Some(h2)),
             player3: (bt3, Some(h3)),
-            win: Some(win),
+            outcome: Some(outcome),
+            enemy: match enemy {
+                Some([e1, e2, e3]) => [Some(e1), Some(e2), Some(e3)],
+                None => [None; 3],
+            },
+            pools: Default::default(),
         }
     }
 }
@@ -83,7 +111,17 @@ impl CompBuilder {
             player1: (roster.0, None),
             player2: (roster.1, None),
             player3: (roster.2, None),
-            win: None,
+            outcome: None,
+            enemy: [None; 3],
+            pools: Default::default(),
+        }
+    }
+
+    /// An empty builder for the next round, keeping the roster and hero pools.
+    pub fn next_round(&self) -> CompBuilder {
+        CompBuilder {
+            pools: self.pools.clone(),
+            ..CompBuilder::new(self.roster())
         }
     }
 
@@ -116,7 +154,7 @@ impl CompBuilder {
     }
 
     pub fn get_hero(&self, player: Player) -> Option<Hero> {
-        self.get_player(player).1.clone()
+        self.get_player(player).1
     }
 
     pub fn clear_hero(&mut self, player: Player) {
@@ -127,16 +165,85 @@ impl CompBuilder {
         &self.get_player(player).0
     }
 
-    pub fn set_win(&mut self, win: bool) {
-        self.win = Some(win)
+    pub fn set_enemy(&mut self, slot: Player, hero: Hero) {
+        self.enemy[slot.index()] = Some(hero)
+    }
+
+    pub fn get_enemy(&self, slot: Player) -> Option<Hero> {
+        self.enemy[slot.index()]
+    }
+
+    pub fn clear_enemy(&mut self, slot: Player) {
+        self.enemy[slot.index()] = None
+    }
+
+    pub fn set_outcome(&mut self, outcome: RoundOutcome) {
+        self.outcome = Some(outcome)
+    }
+
+    pub fn clear_outcome(&mut self) {
+        self.outcome = None
+    }
+
+    pub fn get_outcome(&self) -> Option<RoundOutcome> {
+        self.outcome
+    }
+
+    /// Restrict the heroes `player` may be given, `None` lifts the restriction.
+    pub fn set_pool(&mut self, player: Player, pool: Option<HeroPool>) {
+        self.pools[player.index()] = pool;
+    }
+
+    pub fn get_pool(&self, player: Player) -> Option<&HeroPool> {
+        self.pools[player.index()].as_ref()
+    }
+
+    /// Heroes `player` may pick this round: allowed by the rules of `history` and their
+    /// pool, and not already picked by a teammate.
+    pub fn available_heros(&self, history: &Match, player: Player) -> HeroPool {
+        let mut available = history.available_heros(player);
+        if let Some(pool) = self.get_pool(player) {
+            available = &available & pool;
+        }
+        for other in Player::iter().filter(|p| *p != player) {
+            if let Some(hero) = self.get_hero(other) {
+                available.remove(&hero);
+            }
+        }
+        available
+    }
+
+    /// Heroes the enemy in `slot` may still be recorded as playing this round.
+    pub fn available_enemy_heros(&self, history: &Match, slot: Player) -> HeroPool {
+        let mut available = history.available_enemy_heros(slot);
+        for other in Player::iter().filter(|p| *p != slot) {
+            if let Some(hero) = self.get_enemy(other) {
+                available.remove(&hero);
+            }
+        }
+        available
     }
 
-    pub fn clear_win(&mut self) {
-        self.win = None
+    /// Every comp this round can still be completed to, keeping the heroes already picked.
+    pub fn legal_comps(&self, history: &Match) -> Vec<[Hero; 3]> {
+        comps(Player::iter().map(|p| {
+            let mut available = self.available_heros(history, p);
+            if let Some(hero) = self.get_hero(p) {
+                available.retain(|h| *h == hero);
+            }
+            available
+        }))
     }
 
-    pub fn get_win(&self) -> Option<bool> {
-        self.win
+    /// Whether `comp` is one of `legal_comps`, without listing them all.
+    pub fn is_legal_comp(&self, history: &Match, comp: [Hero; 3]) -> bool {
+        let distinct = comp[0] != comp[1] && comp[0] != comp[2] && comp[1] != comp[2];
+        distinct
+            && Player::iter().all(|p| {
+                let hero = comp[p.index()];
+                self.get_hero(p).unwrap_or(hero) == hero
+                    && self.available_heros(history, p).contains(&hero)
+            })
     }
 
     pub fn validate(&self) -> bool {
@@ -145,11 +252,20 @@ impl CompBuilder {
                 return false;
             }
         }
-        self.win.is_some()
+        let enemies = self.enemy.iter().filter(|h| h.is_some()).count();
+        self.outcome.is_some() && (enemies == 0 || enemies == 3)
     }
 
     pub fn finalize(self) -> Result<Round, MatchHistoryError> {
-        let win = self.win.ok_or_else(|| MatchHistoryError::MissingOutcome)?;
+        let outcome = self.outcome.ok_or(MatchHistoryError::MissingOutcome)?;
+        let enemy = match self.enemy {
+            [Some(e1), Some(e2), Some(e3)] => Some([e1, e2, e3]),
+            [None, None, None] => None,
+            slots => {
+                let missing = Player::iter().find(|p| slots[p.index()].is_none());
+                return Err(MatchHistoryError::MissingEnemyHero(missing.unwrap()));
+            },
+        };
 
         let CompBuilder {
             player1: (b1, h1),
@@ -162,7 +278,8 @@ impl CompBuilder {
             player1: finalize_player(Player::One, b1, h1)?,
             player2: finalize_player(Player::Two, b2, h2)?,
             player3: finalize_player(Player::Three, b3, h3)?,
-            win,
+            outcome,
+            enemy,
         })
     }
 }
@@ -179,29 +296,158 @@ fn finalize_player(
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Match {
+    rules: GameRules,
+    map: Option<Map>,
     rounds: Vec<Round>,
 }
 
 impl Match {
+    pub fn new(rules: GameRules) -> Match {
+        Match {
+            rules,
+            map: None,
+            rounds: vec![],
+        }
+    }
+
+    pub fn rules(&self) -> &GameRules {
+        &self.rules
+    }
+
+    pub fn map(&self) -> Option<Map> {
+        self.map
+    }
+
+    pub fn set_map(&mut self, map: Option<Map>) {
+        self.map = map
+    }
+
+    /// Heroes nobody on the team can pick any more.
     pub fn used_heros(&self) -> HeroPool {
+        self.team_used_heros(Team::Ally)
+    }
+
+    /// Heroes `player` can no longer pick under the match's hero lock.
+    pub fn used_heros_for(&self, player: Player) -> HeroPool {
+        self.locked_heros(Team::Ally, player)
+    }
+
+    /// Heroes nobody on the enemy team can pick any more, as far as we have recorded.
+    pub fn used_enemy_heros(&self) -> HeroPool {
+        self.team_used_heros(Team::Enemy)
+    }
+
+    pub fn used_enemy_heros_for(&self, slot: Player) -> HeroPool {
+        self.locked_heros(Team::Enemy, slot)
+    }
+
+    /// Heroes `player` may still pick under the match's hero lock.
+    pub fn available_heros(&self, player: Player) -> HeroPool {
+        self.team_available_heros(Team::Ally, player)
+    }
+
+    pub fn available_enemy_heros(&self, slot: Player) -> HeroPool {
+        self.team_available_heros(Team::Enemy, slot)
+    }
+
+    /// Every comp the team may field in the next round.
+    pub fn legal_comps(&self) -> Vec<[Hero; 3]> {
+        comps(Player::iter().map(|p| self.available_heros(p)))
+    }
+
+    fn team_available_heros(&self, team: Team, player: Player) -> HeroPool {
+        HEROPOOL
+            .difference(&self.locked_heros(team, player))
+            .cloned()
+            .collect()
+    }
+
+    fn team_used_heros(&self, team: Team) -> HeroPool {
+        let mut used = Player::iter().map(|p| self.locked_heros(team, p));
+        let first = used.next().unwrap_or_default();
+        used.fold(first, |acc, pool| &acc & &pool)
+    }
+
+    fn locked_heros(&self, team: Team, player: Player) -> HeroPool {
         self.rounds
             .iter()
-            .filter_map(|r| if r.win { Some(r.iter()) } else { None })
-            .flatten()
+            .filter_map(|r| r.team_heros(team).map(|heros| (r.team_won(team), heros)))
+            .flat_map(|(won, heros)| {
+                Player::iter()
+                    .filter(move |p| self.rules.locks(won, player, *p))
+                    .map(move |p| heros[p.index()])
+            })
             .collect()
     }
 
     pub fn insert_round(&mut self, round: Round) -> Result<(), MatchHistoryError> {
-        if self.rounds.len() > 4 {
-            return Err(MatchHistoryError::TooManyRounds);
+        if self.match_outcome().is_some() {
+            return Err(MatchHistoryError::MatchOver);
         }
         self.get_duplicate(&round)?;
         self.rounds.push(round);
         Ok(())
     }
 
+    pub fn get_round(&self, idx: usize) -> Option<&Round> {
+        self.rounds.get(idx)
+    }
+
+    /// Swap the round at `idx` for `round`, returning the old one.
+    ///
+    /// Every later round is checked again against the new locks, and the match is left
+    /// untouched if any of them would become invalid.
+    pub fn replace_round(&mut self, idx: usize, round: Round) -> Result<Round, MatchHistoryError> {
+        let mut rounds = self.rounds.clone();
+        let slot = rounds.get_mut(idx).ok_or(MatchHistoryError::NoSuchRound(idx))?;
+        let old = mem::replace(slot, round);
+        self.replay(rounds)?;
+        Ok(old)
+    }
+
+    /// Remove the round at `idx`, revalidating every later round.
+    pub fn remove_round(&mut self, idx: usize) -> Result<Round, MatchHistoryError> {
+        if idx >= self.rounds.len() {
+            return Err(MatchHistoryError::NoSuchRound(idx));
+        }
+        let mut rounds = self.rounds.clone();
+        let old = rounds.remove(idx);
+        self.replay(rounds)?;
+        Ok(old)
+    }
+
+    /// Move the round at `from` to position `to`, shifting the rounds in between.
+    pub fn move_round(&mut self, from: usize, to: usize) -> Result<(), MatchHistoryError> {
+        let len = self.rounds.len();
+        if from >= len {
+            return Err(MatchHistoryError::NoSuchRound(from));
+        } else if to >= len {
+            return Err(MatchHistoryError::NoSuchRound(to));
+        }
+        let mut rounds = self.rounds.clone();
+        let round = rounds.remove(from);
+        rounds.insert(to, round);
+        self.replay(rounds)
+    }
+
+    /// Replace all rounds with `rounds` if they form a valid match, reporting the first
+    /// round that does not.
+    fn replay(&mut self, rounds: Vec<Round>) -> Result<(), MatchHistoryError> {
+        let mut replayed = Match {
+            map: self.map,
+            ..Match::new(self.rules.clone())
+        };
+        for (idx, round) in rounds.into_iter().enumerate() {
+            replayed
+                .insert_round(round)
+                .map_err(|e| MatchHistoryError::InvalidRound(idx, Box::new(e)))?;
+        }
+        *self = replayed;
+        Ok(())
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &Round> {
         self.rounds.iter()
     }
@@ -210,63 +456,93 @@ impl Match {
         self.rounds.len()
     }
 
-    pub fn match_outcome(&self) -> Option<bool> {
+    pub fn is_empty(&self) -> bool {
+        self.rounds.is_empty()
+    }
+
+    pub fn match_outcome(&self) -> Option<MatchOutcome> {
         let mut wins = 0;
         let mut loss = 0;
-        for r in &self.rounds {
-            if r.win {
-                wins += 1;
-            } else {
-                loss += 1;
+        for (idx, r) in self.rounds.iter().enumerate() {
+            match r.outcome {
+                RoundOutcome::Win => wins += 1,
+                RoundOutcome::Loss => loss += 1,
+                RoundOutcome::Draw => {},
             }
-            if wins >= 3 {
-                return Some(true);
-            } else if loss >= 3 {
-                return Some(false);
+            if let Some(outcome) = self.rules.outcome(idx + 1, wins, loss) {
+                return Some(outcome);
             }
         }
         None
     }
 
     fn get_duplicate(&self, round: &Round) -> Result<(), MatchHistoryError> {
-        self.rounds
-            .iter()
-            .filter_map(|r| if r.win { Some(r.iter()) } else { None })
-            .flatten()
-            .chain(round.iter())
-            .try_fold(HashSet::new(), |mut uniq, h: Hero| {
-                if !uniq.insert(h) {
-                    Err(MatchHistoryError::DuplicateHero(h))
-                } else {
-                    Ok(uniq)
+        for team in &[Team::Ally, Team::Enemy] {
+            let heros = match round.team_heros(*team) {
+                Some(heros) => heros,
+                None => continue,
+            };
+            let mut uniq = HashSet::new();
+            for player in Player::iter() {
+                let hero = heros[player.index()];
+                if !uniq.insert(hero) || !self.team_available_heros(*team, player).contains(&hero) {
+                    return Err(match team {
+                        Team::Ally => MatchHistoryError::DuplicateHero(hero),
+                        Team::Enemy => MatchHistoryError::DuplicateEnemyHero(hero),
+                    });
                 }
-            })
-            .map(|_hp| ())
+            }
+        }
+        Ok(())
     }
 }
 
+/// All comps of distinct heroes, taking each player's hero from their pool, in hero order.
+fn comps(pools: impl Iterator<Item = HeroPool>) -> Vec<[Hero; 3]> {
+    let pools = pools
+        .map(|pool| {
+            let mut pool = pool.into_iter().collect::<Vec<_>>();
+            pool.sort();
+            pool
+        })
+        .collect::<Vec<_>>();
+
+    let mut comps = Vec::new();
+    for &h1 in &pools[0] {
+        for &h2 in pools[1].iter().filter(|h| **h != h1) {
+            for &h3 in pools[2].iter().filter(|h| **h != h1 && **h != h2) {
+                comps.push([h1, h2, h3]);
+            }
+        }
+    }
+    comps
+}
+
 #[cfg(test)]
 mod tests {
     use crate::overwatch_3v3::Player;
-    use crate::overwatch_3v3::{CompBuilder, Match, MatchHistoryError, Round};
+    use crate::overwatch_3v3::{
+        CompBuilder, GameRules, HeroLock, Match, MatchHistoryError, MatchOutcome, Round,
+        RoundOutcome,
+    };
     use crate::{BattleTag, Hero};
 
     fn catch_duplicate(r: Result<(), MatchHistoryError>, hero: Hero) {
         match r {
             Ok(_) => panic!("duplicate not detected!"),
             Err(MatchHistoryError::DuplicateHero(h)) => assert_eq!(h, hero),
-            Err(e) => Err(e).expect("incorrect error type"),
+            Err(e) => panic!("incorrect error type: {:?}", e),
         }
     }
 
     #[test]
     fn no_duplicate_two_rounds() {
-        let mut m = Match { rounds: vec![] };
+        let mut m = Match::default();
         let mut rb1 = CompBuilder::default();
         rb1.set_player(Player::One, Hero::Mercy);
         rb1.set_player(Player::Two, Hero::Pharah);
         rb1.set_player(Player::Three, Hero::Soldier76);
-        rb1.set_win(true);
+        rb1.set_outcome(RoundOutcome::Win);
 
         let r1 = rb1.finalize().unwrap();
         m.insert_round(r1).unwrap();
@@ -275,19 +551,19 @@ mod tests {
         rb2.set_player(Player::One, Hero::Roadhog);
         rb2.set_player(Player::Two, Hero::Mei);
         rb2.set_player(Player::Three, Hero::Brigitte);
-        rb2.set_win(true);
+        rb2.set_outcome(RoundOutcome::Win);
         let r2 = rb2.finalize().unwrap();
         m.insert_round(r2).unwrap();
     }
 
     #[test]
     fn duplicate_in_single_winning_round() {
-        let mut m = Match { rounds: vec![] };
+        let mut m = Match::default();
         let mut rb1 = CompBuilder::default();
         rb1.set_player(Player::One, Hero::Mercy);
         rb1.set_player(Player::Two, Hero::Pharah);
         rb1.set_player(Player::Three, Hero::Pharah);
-        rb1.set_win(true);
+        rb1.set_outcome(RoundOutcome::Win);
 
         let r1 = rb1.finalize().unwrap();
         catch_duplicate(m.insert_round(r1), Hero::Pharah)
@@ -295,12 +571,12 @@ mod tests {
 
     #[test]
     fn duplicate_in_single_losing_round() {
-        let mut m = Match { rounds: vec![] };
+        let mut m = Match::default();
         let mut rb1 = CompBuilder::default();
         rb1.set_player(Player::One, Hero::Mercy);
         rb1.set_player(Player::Two, Hero::Pharah);
         rb1.set_player(Player::Three, Hero::Pharah);
-        rb1.set_win(false);
+        rb1.set_outcome(RoundOutcome::Loss);
 
         let r1 = rb1.finalize().unwrap();
         catch_duplicate(m.insert_round(r1), Hero::Pharah)
@@ -308,12 +584,12 @@ mod tests {
 
     #[test]
     fn no_duplicate_in_two_losing_rounds() {
-        let mut m = Match { rounds: vec![] };
+        let mut m = Match::default();
         let mut rb1 = CompBuilder::default();
         rb1.set_player(Player::One, Hero::Mercy);
         rb1.set_player(Player::Two, Hero::Pharah);
         rb1.set_player(Player::Three, Hero::Soldier76);
-        rb1.set_win(false);
+        rb1.set_outcome(RoundOutcome::Loss);
         let r1 = rb1.finalize().unwrap();
         m.insert_round(r1).unwrap();
 
@@ -321,7 +597,7 @@ mod tests {
         rb2.set_player(Player::One, Hero::Mercy);
         rb2.set_player(Player::Two, Hero::Pharah);
         rb2.set_player(Player::Three, Hero::Soldier76);
-        rb2.set_win(false);
+        rb2.set_outcome(RoundOutcome::Loss);
         let r2 = rb2.finalize().unwrap();
         m.insert_round(r2).unwrap();
     }
@@ -329,24 +605,29 @@ mod tests {
     #[test]
     fn match_used() {
         let m = Match {
+            rules: GameRules::lockout(),
+            map: None,
             rounds: vec![
                 Round {
                     player1: (BattleTag::new("a"), Hero::Ana),
                     player2: (BattleTag::new("a"), Hero::Zenyatta),
                     player3: (BattleTag::new("a"), Hero::Sigma),
-                    win: false,
+                    outcome: RoundOutcome::Loss,
+                    enemy: None,
                 },
                 Round {
                     player1: (BattleTag::new("a"), Hero::Roadhog),
                     player2: (BattleTag::new("a"), Hero::Brigitte),
                     player3: (BattleTag::new("a"), Hero::Mei),
-                    win: true,
+                    outcome: RoundOutcome::Win,
+                    enemy: None,
                 },
                 Round {
                     player1: (BattleTag::new("a"), Hero::Pharah),
                     player2: (BattleTag::new("a"), Hero::Soldier76),
                     player3: (BattleTag::new("a"), Hero::Mercy),
-                    win: true,
+                    outcome: RoundOutcome::Win,
+                    enemy: None,
                 },
             ],
         };
@@ -365,4 +646,239 @@ mod tests {
             .collect()
         )
     }
+
+    fn round(heros: [Hero; 3], outcome: RoundOutcome) -> Round {
+        Round {
+            player1: (BattleTag::new("a"), heros[0]),
+            player2: (BattleTag::new("b"), heros[1]),
+            player3: (BattleTag::new("c"), heros[2]),
+            outcome,
+            enemy: None,
+        }
+    }
+
+    #[test]
+    fn elimination_allows_repeat_winners() {
+        let mut m = Match::new(GameRules::elimination());
+        let comp = [Hero::Pharah, Hero::Mercy, Hero::Reinhardt];
+        m.insert_round(round(comp, RoundOutcome::Win)).unwrap();
+        m.insert_round(round(comp, RoundOutcome::Win)).unwrap();
+        assert!(m.used_heros().is_empty());
+    }
+
+    #[test]
+    fn per_player_lock() {
+        let mut m = Match::new(GameRules {
+            hero_lock: HeroLock::PerPlayer,
+            ..GameRules::lockout()
+        });
+        m.insert_round(round(
+            [Hero::Pharah, Hero::Mercy, Hero::Reinhardt],
+            RoundOutcome::Loss,
+        ))
+        .unwrap();
+        m.insert_round(round(
+            [Hero::Mercy, Hero::Pharah, Hero::Sigma],
+            RoundOutcome::Win,
+        ))
+        .unwrap();
+        catch_duplicate(
+            m.insert_round(round(
+                [Hero::Ana, Hero::Lucio, Hero::Reinhardt],
+                RoundOutcome::Win,
+            )),
+            Hero::Reinhardt,
+        );
+        assert!(m.used_heros().is_empty());
+        assert!(m.used_heros_for(Player::One).contains(&Hero::Mercy));
+        assert!(!m.used_heros_for(Player::One).contains(&Hero::Reinhardt));
+    }
+
+    #[test]
+    fn all_played_lock() {
+        let mut m = Match::new(GameRules {
+            hero_lock: HeroLock::AllPlayed,
+            ..GameRules::lockout()
+        });
+        m.insert_round(round(
+            [Hero::Pharah, Hero::Mercy, Hero::Reinhardt],
+            RoundOutcome::Loss,
+        ))
+        .unwrap();
+        catch_duplicate(
+            m.insert_round(round(
+                [Hero::Ana, Hero::Mercy, Hero::Sigma],
+                RoundOutcome::Loss,
+            )),
+            Hero::Mercy,
+        );
+    }
+
+    #[test]
+    fn no_rounds_after_outcome() {
+        let mut m = Match::new(GameRules {
+            rounds_to_win: 2,
+            max_rounds: 3,
+            ..GameRules::elimination()
+        });
+        let comp = [Hero::Pharah, Hero::Mercy, Hero::Reinhardt];
+        m.insert_round(round(comp, RoundOutcome::Win)).unwrap();
+        assert_eq!(m.match_outcome(), None);
+        m.insert_round(round(comp, RoundOutcome::Win)).unwrap();
+        assert_eq!(m.match_outcome(), Some(MatchOutcome::Win));
+        match m.insert_round(round(comp, RoundOutcome::Loss)) {
+            Err(MatchHistoryError::MatchOver) => {},
+            r => panic!("expected match over error: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn draws_do_not_score() {
+        let mut m = Match::default();
+        let comps = [
+            [Hero::Pharah, Hero::Mercy, Hero::Reinhardt],
+            [Hero::Ana, Hero::Lucio, Hero::Sigma],
+            [Hero::Mei, Hero::Zenyatta, Hero::Roadhog],
+            [Hero::Genji, Hero::Moira, Hero::Winston],
+        ];
+        m.insert_round(round(comps[0], RoundOutcome::Draw)).unwrap();
+        m.insert_round(round(comps[0], RoundOutcome::Win)).unwrap();
+        m.insert_round(round(comps[1], RoundOutcome::Win)).unwrap();
+        m.insert_round(round(comps[2], RoundOutcome::Draw)).unwrap();
+        assert_eq!(m.match_outcome(), None);
+        m.insert_round(round(comps[3], RoundOutcome::Loss)).unwrap();
+        assert_eq!(m.match_outcome(), Some(MatchOutcome::Win));
+    }
+
+    #[test]
+    fn enemy_winners_locked() {
+        let mut m = Match::default();
+        let mut r1 = round(
+            [Hero::Pharah, Hero::Mercy, Hero::Reinhardt],
+            RoundOutcome::Loss,
+        );
+        r1.enemy = Some([Hero::Ana, Hero::Lucio, Hero::Sigma]);
+        m.insert_round(r1).unwrap();
+        assert_eq!(
+            m.used_enemy_heros(),
+            [Hero::Ana, Hero::Lucio, Hero::Sigma]
+                .iter()
+                .cloned()
+                .collect()
+        );
+
+        let mut r2 = round(
+            [Hero::Pharah, Hero::Mercy, Hero::Reinhardt],
+            RoundOutcome::Win,
+        );
+        r2.enemy = Some([Hero::Mei, Hero::Lucio, Hero::Winston]);
+        match m.insert_round(r2) {
+            Err(MatchHistoryError::DuplicateEnemyHero(h)) => assert_eq!(h, Hero::Lucio),
+            r => panic!("expected duplicate enemy hero: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn partial_enemy_comp() {
+        let mut rb = CompBuilder::default();
+        rb.set_player(Player::One, Hero::Mercy);
+        rb.set_player(Player::Two, Hero::Pharah);
+        rb.set_player(Player::Three, Hero::Soldier76);
+        rb.set_outcome(RoundOutcome::Win);
+        rb.set_enemy(Player::One, Hero::Ana);
+        rb.set_enemy(Player::Three, Hero::Sigma);
+        assert!(!rb.validate());
+        match rb.finalize() {
+            Err(MatchHistoryError::MissingEnemyHero(p)) => assert_eq!(p, Player::Two),
+            r => panic!("expected missing enemy hero: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn available_heros_follow_rules() {
+        let mut m = Match::new(GameRules {
+            hero_lock: HeroLock::PerPlayer,
+            ..GameRules::lockout()
+        });
+        m.insert_round(round([Hero::Ana, Hero::Mei, Hero::Sigma], RoundOutcome::Loss))
+            .unwrap();
+        assert!(!m.available_heros(Player::One).contains(&Hero::Ana));
+        assert!(m.available_heros(Player::Two).contains(&Hero::Ana));
+        for comp in m.legal_comps() {
+            assert!(comp[0] != Hero::Ana && comp[1] != Hero::Mei && comp[2] != Hero::Sigma);
+        }
+    }
+
+    #[test]
+    fn legal_comps_respect_picks_and_pools() {
+        let history = Match::default();
+        let mut rb = CompBuilder::default();
+        rb.set_player(Player::One, Hero::Ana);
+        rb.set_pool(Player::Two, Some([Hero::Ana, Hero::Mei].iter().cloned().collect()));
+        rb.set_pool(Player::Three, Some([Hero::Mei, Hero::Sigma].iter().cloned().collect()));
+
+        assert!(!rb.available_heros(&history, Player::Two).contains(&Hero::Ana));
+        assert_eq!(rb.legal_comps(&history), vec![[Hero::Ana, Hero::Mei, Hero::Sigma]]);
+        assert!(rb.is_legal_comp(&history, [Hero::Ana, Hero::Mei, Hero::Sigma]));
+        assert!(!rb.is_legal_comp(&history, [Hero::Ana, Hero::Mei, Hero::Mei]));
+        assert!(!rb.is_legal_comp(&history, [Hero::Mercy, Hero::Mei, Hero::Sigma]));
+        assert!(!rb.is_legal_comp(&history, [Hero::Ana, Hero::Sigma, Hero::Mei]));
+
+        let next = rb.next_round();
+        assert_eq!(next.get_hero(Player::One), None);
+        assert_eq!(next.get_pool(Player::Two), rb.get_pool(Player::Two));
+    }
+
+    #[test]
+    fn replace_revalidates_later_rounds() {
+        let mut m = Match::default();
+        m.insert_round(round([Hero::Ana, Hero::Mei, Hero::Sigma], RoundOutcome::Loss))
+            .unwrap();
+        m.insert_round(round([Hero::Ana, Hero::Genji, Hero::Lucio], RoundOutcome::Win))
+            .unwrap();
+
+        // winning the first round locks Ana for the second
+        let win = round([Hero::Ana, Hero::Mei, Hero::Sigma], RoundOutcome::Win);
+        match m.replace_round(0, win) {
+            Err(MatchHistoryError::InvalidRound(1, e)) => match *e {
+                MatchHistoryError::DuplicateHero(h) => assert_eq!(h, Hero::Ana),
+                e => panic!("expected duplicate hero: {:?}", e),
+            },
+            r => panic!("expected invalid round: {:?}", r),
+        }
+        assert_eq!(m.get_round(0).unwrap().outcome, RoundOutcome::Loss);
+
+        let draw = round([Hero::Mercy, Hero::Mei, Hero::Sigma], RoundOutcome::Draw);
+        let old = m.replace_round(0, draw).unwrap();
+        assert_eq!(old.player1.1, Hero::Ana);
+        assert_eq!(m.get_round(0).unwrap().player1.1, Hero::Mercy);
+
+        match m.replace_round(2, old) {
+            Err(MatchHistoryError::NoSuchRound(2)) => {},
+            r => panic!("expected missing round: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn remove_and_move_rounds() {
+        let mut m = Match::default();
+        m.insert_round(round([Hero::Ana, Hero::Mei, Hero::Sigma], RoundOutcome::Win))
+            .unwrap();
+        m.insert_round(round([Hero::Genji, Hero::Lucio, Hero::Mercy], RoundOutcome::Loss))
+            .unwrap();
+        m.insert_round(round([Hero::Genji, Hero::Lucio, Hero::Mercy], RoundOutcome::Win))
+            .unwrap();
+
+        // the loss can't come after the win with the same heroes
+        match m.move_round(1, 2) {
+            Err(MatchHistoryError::InvalidRound(2, _)) => {},
+            r => panic!("expected invalid round: {:?}", r),
+        }
+        m.move_round(0, 2).unwrap();
+        assert_eq!(m.get_round(2).unwrap().player1.1, Hero::Ana);
+
+        let removed = m.remove_round(0).unwrap();
+        assert_eq!(removed.outcome, RoundOutcome::Loss);
+        assert_eq!(m.len(), 2);
+    }
 }
@@ -0,0 +1,325 @@
+use super::recommend::{score_legal_comps, sort_best};
+use super::{
+    CompBuilder, CompScorer, Match, MatchOutcome, Player, Roster, Round, RoundOutcome, Suggestion,
+};
+use crate::Hero;
+use std::collections::HashMap;
+use std::fmt;
+
+/// How many of the best legal comps the planner considers for each round.
+pub const DEFAULT_BREADTH: usize = 6;
+
+/// One round of a `Plan`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanStep {
+    /// Indexed by `Player::index`.
+    pub comp: [Hero; 3],
+    /// Chance of winning this round with `comp`.
+    pub round_win: f64,
+    /// Chance of winning the match from the start of this round, following the plan.
+    pub match_win: f64,
+    /// Chance of still winning the match if this round is lost.
+    pub match_win_if_lost: f64,
+}
+
+/// The comps to play for each win we still need, in order.
+///
+/// Under hero locks a lost round usually leaves the plan intact, so the steps follow
+/// the line where every round is won. After a loss, plan again.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Plan {
+    pub match_win: f64,
+    pub steps: Vec<PlanStep>,
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "match win {:.0}%", self.match_win * 100.0)?;
+        for (idx, step) in self.steps.iter().enumerate() {
+            let [h1, h2, h3] = step.comp;
+            writeln!(
+                f,
+                "{}. {} / {} / {}: round {:.0}%, match {:.0}% ({:.0}% if lost)",
+                idx + 1,
+                h1,
+                h2,
+                h3,
+                step.round_win * 100.0,
+                step.match_win * 100.0,
+                step.match_win_if_lost * 100.0,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Plan the remaining rounds of `history` to maximise the chance of winning the match.
+///
+/// Heroes already picked in `builder` are ignored, but its hero pools hold for
+/// every round. Scores from `scorer` are read as round win probabilities. Each round only the
+/// `breadth` best comps still legal under the match's hero lock are tried, which keeps
+/// the search small while still letting the plan hold a strong comp back for later.
+pub fn plan<S: CompScorer + ?Sized>(
+    scorer: &S,
+    history: &Match,
+    builder: &CompBuilder,
+    breadth: usize,
+) -> Plan {
+    let roster = builder.roster();
+    let mut planner = Planner {
+        roster: &roster,
+        ranked: score_legal_comps(scorer, history, &builder.next_round()),
+        sorted: 0,
+        breadth,
+        memo: HashMap::new(),
+    };
+
+    let mut plan = Plan {
+        match_win: planner.value(history),
+        steps: Vec::new(),
+    };
+    let mut state = history.clone();
+    while let Some(node) = planner.best(&state) {
+        let step = PlanStep {
+            comp: node.comp,
+            round_win: node.round_win,
+            match_win: node.value,
+            match_win_if_lost: planner.value(&planner.play(&state, node.comp, RoundOutcome::Loss)),
+        };
+        state = planner.play(&state, node.comp, RoundOutcome::Win);
+        plan.steps.push(step);
+    }
+    plan
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    comp: [Hero; 3],
+    round_win: f64,
+    value: f64,
+}
+
+/// Hero locks per player plus the score; everything the rest of the match depends on.
+type StateKey = (Vec<Vec<Hero>>, usize, usize, usize);
+
+struct Planner<'a> {
+    roster: &'a Roster,
+    /// Every comp legal now, best first up to `sorted` and in no particular order after.
+    ///
+    /// Later rounds only lock more heroes, so the best comps then are the best of these that
+    /// are still open. Usually a short prefix has enough of them, so the rest is only
+    /// sorted when the locks run through it.
+    ranked: Vec<Suggestion>,
+    sorted: usize,
+    breadth: usize,
+    memo: HashMap<StateKey, Option<Node>>,
+}
+
+impl<'a> Planner<'a> {
+    fn value(&mut self, state: &Match) -> f64 {
+        match state.match_outcome() {
+            Some(MatchOutcome::Win) => 1.0,
+            Some(_) => 0.0,
+            None => self.best(state).map(|n| n.value).unwrap_or(0.0),
+        }
+    }
+
+    /// The best comp for the next round, or `None` once the match is over.
+    fn best(&mut self, state: &Match) -> Option<Node> {
+        if state.match_outcome().is_some() {
+            return None;
+        }
+        let key = key(state);
+        if let Some(node) = self.memo.get(&key) {
+            return *node;
+        }
+
+        let mut best: Option<Node> = None;
+        for comp in self.candidates(state) {
+            let (comp, round_win) = (comp.comp, comp.score.clamp(0.0, 1.0));
+            let won = self.value(&self.play(state, comp, RoundOutcome::Win));
+            let lost = self.value(&self.play(state, comp, RoundOutcome::Loss));
+            let value = round_win * won + (1.0 - round_win) * lost;
+            if best.map(|b| value > b.value).unwrap_or(true) {
+                best = Some(Node {
+                    comp,
+                    round_win,
+                    value,
+                });
+            }
+        }
+        self.memo.insert(key, best);
+        best
+    }
+
+    fn candidates(&mut self, state: &Match) -> Vec<Suggestion> {
+        let available = Player::iter()
+            .map(|p| state.available_heros(p))
+            .collect::<Vec<_>>();
+        loop {
+            let candidates = self.ranked[..self.sorted]
+                .iter()
+                .filter(|s| {
+                    Player::iter().all(|p| available[p.index()].contains(&s.comp[p.index()]))
+                })
+                .take(self.breadth)
+                .cloned()
+                .collect::<Vec<_>>();
+            if candidates.len() == self.breadth || self.sorted == self.ranked.len() {
+                return candidates;
+            }
+            self.sort_more();
+        }
+    }
+
+    /// Sort the next stretch of `ranked`, twice as long as the last.
+    fn sort_more(&mut self) {
+        let more = self.sorted.max(self.breadth);
+        sort_best(&mut self.ranked[self.sorted..], more);
+        self.sorted = (self.sorted + more).min(self.ranked.len());
+    }
+
+    fn play(&self, state: &Match, comp: [Hero; 3], outcome: RoundOutcome) -> Match {
+        let Roster(p1, p2, p3) = self.roster;
+        let mut next = state.clone();
+        next.insert_round(Round {
+            player1: (p1.clone(), comp[0]),
+            player2: (p2.clone(), comp[1]),
+            player3: (p3.clone(), comp[2]),
+            outcome,
+            enemy: None,
+        })
+        .expect("candidate comps are legal");
+        next
+    }
+}
+
+fn key(state: &Match) -> StateKey {
+    let locked = Player::iter()
+        .map(|p| {
+            let mut heros = state.used_heros_for(p).into_iter().collect::<Vec<_>>();
+            heros.sort();
+            heros
+        })
+        .collect();
+    let wins = state
+        .iter()
+        .filter(|r| r.outcome == RoundOutcome::Win)
+        .count();
+    let losses = state
+        .iter()
+        .filter(|r| r.outcome == RoundOutcome::Loss)
+        .count();
+    (locked, wins, losses, state.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::overwatch_3v3::GameRules;
+
+    /// Ana/Mei/Sigma is a near-certain win, anything with Ana a coin flip, the rest
+    /// hopeless.
+    struct StarScorer;
+
+    impl CompScorer for StarScorer {
+        fn score(&self, _roster: &Roster, comp: [Hero; 3]) -> f64 {
+            if comp == [Hero::Ana, Hero::Mei, Hero::Sigma] {
+                0.9
+            } else if comp.contains(&Hero::Ana) {
+                0.5
+            } else {
+                0.1
+            }
+        }
+    }
+
+    #[test]
+    fn plan_covers_needed_wins() {
+        let plan = plan(
+            &StarScorer,
+            &Match::default(),
+            &CompBuilder::default(),
+            DEFAULT_BREADTH,
+        );
+        assert_eq!(plan.steps.len(), 3);
+        assert!(plan.match_win > 0.0 && plan.match_win < 1.0);
+        assert_eq!(plan.steps[0].match_win, plan.match_win);
+        // nothing won can be played again under lockout
+        let mut seen = Vec::new();
+        for step in &plan.steps {
+            for hero in &step.comp {
+                assert!(!seen.contains(hero));
+                seen.push(*hero);
+            }
+        }
+    }
+
+    #[test]
+    fn elimination_repeats_best_comp() {
+        let history = Match::new(GameRules::elimination());
+        let plan = plan(
+            &StarScorer,
+            &history,
+            &CompBuilder::default(),
+            DEFAULT_BREADTH,
+        );
+        assert_eq!(plan.steps.len(), 3);
+        for step in &plan.steps {
+            assert_eq!(step.comp, [Hero::Ana, Hero::Mei, Hero::Sigma]);
+        }
+    }
+
+    /// Anything with Ana on player one is likely to win, then Mercy on player one.
+    struct FavouriteScorer;
+
+    impl CompScorer for FavouriteScorer {
+        fn score(&self, _roster: &Roster, comp: [Hero; 3]) -> f64 {
+            match comp[0] {
+                Hero::Ana => 0.9,
+                Hero::Mercy => 0.6,
+                _ => 0.1,
+            }
+        }
+    }
+
+    #[test]
+    fn looks_past_locked_favourites() {
+        // every comp with Ana on player one ranks ahead of the first with Mercy
+        let plan = plan(
+            &FavouriteScorer,
+            &Match::default(),
+            &CompBuilder::default(),
+            DEFAULT_BREADTH,
+        );
+        assert_eq!(plan.steps.len(), 3);
+        assert_eq!(plan.steps[0].comp[0], Hero::Ana);
+        assert_eq!(plan.steps[1].comp[0], Hero::Mercy);
+        assert_eq!(plan.steps[1].round_win, 0.6);
+    }
+
+    #[test]
+    fn finished_match_has_no_plan() {
+        let mut history = Match::new(GameRules::elimination());
+        let roster = Roster::default();
+        for _ in 0..3 {
+            history
+                .insert_round(Round {
+                    player1: (roster.0.clone(), Hero::Ana),
+                    player2: (roster.1.clone(), Hero::Mei),
+                    player3: (roster.2.clone(), Hero::Sigma),
+                    outcome: RoundOutcome::Win,
+                    enemy: None,
+                })
+                .unwrap();
+        }
+        let plan = plan(
+            &StarScorer,
+            &history,
+            &CompBuilder::new(roster),
+            DEFAULT_BREADTH,
+        );
+        assert!(plan.steps.is_empty());
+        assert_eq!(plan.match_win, 1.0);
+    }
+}
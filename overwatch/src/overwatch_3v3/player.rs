@@ -1,19 +1,14 @@
 use enum_iterator::IntoEnumIterator;
 use std::fmt;
 
-#[derive(Debug, Clone, Copy, PartialEq, IntoEnumIterator)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, IntoEnumIterator)]
 pub enum Player {
+    #[default]
     One,
     Two,
     Three,
 }
 
-impl Default for Player {
-    fn default() -> Player {
-        Player::One
-    }
-}
-
 #[derive(Clone, Copy, PartialEq)]
 pub struct NumericPlayer(Player);
 
@@ -0,0 +1,248 @@
+use super::{CompBuilder, Match, Player, Roster};
+use crate::{Hero, HeroPool};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// A source of opinions on how well a comp will do for a roster.
+pub trait CompScorer {
+    /// Higher is better. Heroes are indexed by `Player::index`.
+    fn score(&self, roster: &Roster, comp: [Hero; 3]) -> f64;
+}
+
+/// Scores every comp the same, leaving suggestions in hero order.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NeutralScorer;
+
+impl CompScorer for NeutralScorer {
+    fn score(&self, _roster: &Roster, _comp: [Hero; 3]) -> f64 {
+        0.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    /// Indexed by `Player::index`.
+    pub comp: [Hero; 3],
+    pub score: f64,
+}
+
+/// Rank the legal comps for the next round of `history`, best first.
+///
+/// Heroes already picked in `builder` stay where they are, so suggestions only
+/// fill the empty slots, and players keep to their hero pools.
+pub fn recommend<S: CompScorer + ?Sized>(
+    scorer: &S,
+    history: &Match,
+    builder: &CompBuilder,
+    limit: usize,
+) -> Vec<Suggestion> {
+    let mut suggestions = score_legal_comps(scorer, history, builder);
+    keep_best(&mut suggestions, limit);
+    suggestions
+}
+
+/// Every legal comp for the next round of `history` with its score, in hero order.
+pub(super) fn score_legal_comps<S: CompScorer + ?Sized>(
+    scorer: &S,
+    history: &Match,
+    builder: &CompBuilder,
+) -> Vec<Suggestion> {
+    let roster = builder.roster();
+    builder
+        .legal_comps(history)
+        .into_iter()
+        .map(|comp| Suggestion {
+            comp,
+            score: scorer.score(&roster, comp),
+        })
+        .collect()
+}
+
+/// Best first, and equal scores in hero order.
+pub(super) fn rank(a: &Suggestion, b: &Suggestion) -> Ordering {
+    b.score.total_cmp(&a.score).then_with(|| a.comp.cmp(&b.comp))
+}
+
+/// Move the `limit` best suggestions to the front in order, leaving the rest unsorted.
+pub(super) fn sort_best(suggestions: &mut [Suggestion], limit: usize) {
+    if limit < suggestions.len() {
+        suggestions.select_nth_unstable_by(limit, rank);
+    }
+    let limit = limit.min(suggestions.len());
+    suggestions[..limit].sort_unstable_by(rank);
+}
+
+fn keep_best(suggestions: &mut Vec<Suggestion>, limit: usize) {
+    sort_best(suggestions, limit);
+    suggestions.truncate(limit);
+    suggestions.shrink_to_fit();
+}
+
+/// Ranks comps like `recommend`, but remembers the best comps between picks.
+///
+/// Scoring every legal comp is the slow part, and within a round only the locks from the
+/// recorded rounds decide which comps are legal. When those change it keeps the best comps
+/// overall and the best with each player on each hero, which answers the round up to the
+/// first pick. After a second pick so few comps are left that they are ranked afresh.
+#[derive(Debug, Clone, Default)]
+pub struct Recommender {
+    /// The roster, each player's open heroes and the limit the rankings were kept for.
+    locks: Option<(Roster, [HeroPool; 3], usize)>,
+    best: Vec<Suggestion>,
+    /// Indexed by `Player::index`.
+    best_with: [HashMap<Hero, Vec<Suggestion>>; 3],
+}
+
+impl Recommender {
+    pub fn recommend<S: CompScorer + ?Sized>(
+        &mut self,
+        scorer: &S,
+        history: &Match,
+        builder: &CompBuilder,
+        limit: usize,
+    ) -> Vec<Suggestion> {
+        let open = builder.next_round();
+        let locks = (
+            open.roster(),
+            [
+                open.available_heros(history, Player::One),
+                open.available_heros(history, Player::Two),
+                open.available_heros(history, Player::Three),
+            ],
+            limit,
+        );
+        if self.locks.as_ref() != Some(&locks) {
+            self.rank(scorer, history, &open, limit);
+            self.locks = Some(locks);
+        }
+
+        let picks = Player::iter()
+            .filter_map(|p| builder.get_hero(p).map(|h| (p, h)))
+            .collect::<Vec<_>>();
+        match picks.as_slice() {
+            [] => self.best.clone(),
+            [(player, hero)] => self.best_with[player.index()]
+                .get(hero)
+                .cloned()
+                .unwrap_or_default(),
+            _ => recommend(scorer, history, builder, limit),
+        }
+    }
+
+    fn rank<S: CompScorer + ?Sized>(
+        &mut self,
+        scorer: &S,
+        history: &Match,
+        open: &CompBuilder,
+        limit: usize,
+    ) {
+        let mut scored = score_legal_comps(scorer, history, open);
+        self.best_with = Default::default();
+        for suggestion in &scored {
+            for p in Player::iter() {
+                self.best_with[p.index()]
+                    .entry(suggestion.comp[p.index()])
+                    .or_default()
+                    .push(suggestion.clone());
+            }
+        }
+        for with_hero in self.best_with.iter_mut().flat_map(|m| m.values_mut()) {
+            keep_best(with_hero, limit);
+        }
+        keep_best(&mut scored, limit);
+        self.best = scored;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::overwatch_3v3::{Player, Round, RoundOutcome};
+    use crate::BattleTag;
+
+    /// Likes Ana on anyone, and Sigma on player three.
+    struct AnaScorer;
+
+    impl CompScorer for AnaScorer {
+        fn score(&self, _roster: &Roster, comp: [Hero; 3]) -> f64 {
+            let ana = comp.iter().filter(|h| **h == Hero::Ana).count() as f64;
+            let sigma = if comp[2] == Hero::Sigma { 0.5 } else { 0.0 };
+            ana + sigma
+        }
+    }
+
+    #[test]
+    fn best_comp_first() {
+        let suggestions = recommend(&AnaScorer, &Match::default(), &CompBuilder::default(), 5);
+        assert_eq!(suggestions.len(), 5);
+        assert_eq!(suggestions[0].score, 1.5);
+        assert!(suggestions[0].comp.contains(&Hero::Ana));
+        assert_eq!(suggestions[0].comp[2], Hero::Sigma);
+    }
+
+    #[test]
+    fn respects_locks_and_picks() {
+        let mut history = Match::default();
+        history
+            .insert_round(Round {
+                player1: (BattleTag::new("a"), Hero::Ana),
+                player2: (BattleTag::new("b"), Hero::Mei),
+                player3: (BattleTag::new("c"), Hero::Sigma),
+                outcome: RoundOutcome::Win,
+                enemy: None,
+            })
+            .unwrap();
+        let mut builder = CompBuilder::default();
+        builder.set_player(Player::Two, Hero::Mercy);
+
+        let suggestions = recommend(&AnaScorer, &history, &builder, usize::MAX);
+        assert!(!suggestions.is_empty());
+        for s in &suggestions {
+            assert_eq!(s.comp[1], Hero::Mercy);
+            assert!(!s.comp.contains(&Hero::Ana));
+            assert!(!s.comp.contains(&Hero::Sigma));
+        }
+    }
+
+    /// Counts how often it is asked, to tell a cached ranking from a fresh one.
+    #[derive(Default)]
+    struct CountingScorer(std::cell::Cell<usize>);
+
+    impl CompScorer for CountingScorer {
+        fn score(&self, roster: &Roster, comp: [Hero; 3]) -> f64 {
+            self.0.set(self.0.get() + 1);
+            AnaScorer.score(roster, comp)
+        }
+    }
+
+    #[test]
+    fn recommender_matches_recommend_across_picks() {
+        let scorer = CountingScorer::default();
+        let mut recommender = Recommender::default();
+        let mut history = Match::default();
+        let mut builder = CompBuilder::default();
+
+        let first = recommender.recommend(&scorer, &history, &builder, 5);
+        assert_eq!(first, recommend(&AnaScorer, &history, &builder, 5));
+        let mut kept = recommender.best_with.iter().flat_map(|m| m.values());
+        assert!(kept.all(|ranked| ranked.len() <= 5));
+        let scored = scorer.0.get();
+
+        builder.set_player(Player::Three, Hero::Mei);
+        let picked = recommender.recommend(&scorer, &history, &builder, 5);
+        assert_eq!(picked, recommend(&AnaScorer, &history, &builder, 5));
+        assert_eq!(scorer.0.get(), scored, "a pick rescored the comps");
+
+        builder.set_player(Player::One, Hero::Ana);
+        let second = recommender.recommend(&scorer, &history, &builder, 5);
+        assert_eq!(second, recommend(&AnaScorer, &history, &builder, 5));
+
+        builder.set_player(Player::Two, Hero::Mercy);
+        builder.set_outcome(RoundOutcome::Win);
+        history.insert_round(builder.finalize().unwrap()).unwrap();
+        let builder = CompBuilder::default();
+        let locked = recommender.recommend(&scorer, &history, &builder, 5);
+        assert_eq!(locked, recommend(&AnaScorer, &history, &builder, 5));
+        assert!(scorer.0.get() > scored, "new locks kept the old ranking");
+    }
+}
@@ -1,18 +1,67 @@
 use crate::{BattleTag, Hero};
+use std::str::FromStr;
 
 use super::player::PlayerEnumIterator;
 use crate::overwatch_3v3::Player;
 
-#[derive(Debug)]
+#[derive(Debug, Fail)]
+#[fail(display = "could not parse round outcome from '{}'", _0)]
+pub struct ParseOutcomeError(String);
+
+/// How a single round ended, from our point of view.
+///
+/// Elimination rounds are a draw when the timer runs out with both teams alive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoundOutcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl RoundOutcome {
+    /// The name used for database entries.
+    pub fn key(self) -> &'static str {
+        match self {
+            RoundOutcome::Win => "win",
+            RoundOutcome::Loss => "loss",
+            RoundOutcome::Draw => "draw",
+        }
+    }
+}
+
+impl FromStr for RoundOutcome {
+    type Err = ParseOutcomeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "win" => RoundOutcome::Win,
+            "loss" => RoundOutcome::Loss,
+            "draw" => RoundOutcome::Draw,
+            s => return Err(ParseOutcomeError(s.into())),
+        })
+    }
+}
+
+/// One side of a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Team {
+    #[default]
+    Ally,
+    Enemy,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Round {
     pub player1: (BattleTag, Hero),
     pub player2: (BattleTag, Hero),
     pub player3: (BattleTag, Hero),
-    pub win: bool,
+    pub outcome: RoundOutcome,
+    /// The opposing team's heroes, indexed by `Player::index`, if they were recorded.
+    pub enemy: Option<[Hero; 3]>,
 }
 
 impl Round {
-    pub fn iter(&self) -> RoundIter {
+    pub fn iter(&self) -> RoundIter<'_> {
         self.into_iter()
     }
 
@@ -26,6 +75,25 @@ impl Round {
     pub fn get_hero(&self, player: Player) -> Hero {
         self.get_player(player).1
     }
+
+    pub fn get_enemy_hero(&self, slot: Player) -> Option<Hero> {
+        self.enemy.map(|heros| heros[slot.index()])
+    }
+
+    /// The heroes `team` played, if we know them.
+    pub fn team_heros(&self, team: Team) -> Option<[Hero; 3]> {
+        match team {
+            Team::Ally => Some([self.player1.1, self.player2.1, self.player3.1]),
+            Team::Enemy => self.enemy,
+        }
+    }
+
+    pub fn team_won(&self, team: Team) -> bool {
+        match team {
+            Team::Ally => self.outcome == RoundOutcome::Win,
+            Team::Enemy => self.outcome == RoundOutcome::Loss,
+        }
+    }
 }
 
 impl<'a> IntoIterator for &'a Round {
@@ -35,7 +103,7 @@ impl<'a> IntoIterator for &'a Round {
     fn into_iter(self) -> Self::IntoIter {
         RoundIter {
             player: Player::iter(),
-            round: &self,
+            round: self,
         }
     }
 }
@@ -48,21 +116,19 @@ pub struct RoundIter<'a> {
 impl<'a> Iterator for RoundIter<'a> {
     type Item = Hero;
     fn next(&mut self) -> Option<Hero> {
-        self.player.next().map(|p| {
-            let hero = match p {
-                Player::One => self.round.player1.1,
-                Player::Two => self.round.player2.1,
-                Player::Three => self.round.player3.1,
-            };
-            hero
+        self.player.next().map(|p| match p {
+            Player::One => self.round.player1.1,
+            Player::Two => self.round.player2.1,
+            Player::Three => self.round.player3.1,
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Round;
+    use super::{Round, RoundOutcome};
     use crate::{BattleTag, Hero};
+    use std::str::FromStr;
 
     #[test]
     fn round_iter() {
@@ -70,11 +136,20 @@ mod tests {
             player1: (BattleTag::new("a"), Hero::Roadhog),
             player2: (BattleTag::new("a"), Hero::Brigitte),
             player3: (BattleTag::new("a"), Hero::Mei),
-            win: false,
+            outcome: RoundOutcome::Loss,
+            enemy: None,
         };
         assert_eq!(
             vec![Hero::Roadhog, Hero::Brigitte, Hero::Mei],
             r.iter().collect::<Vec<_>>()
         )
     }
+
+    #[test]
+    fn parse_outcome_keys() {
+        for outcome in &[RoundOutcome::Win, RoundOutcome::Loss, RoundOutcome::Draw] {
+            assert_eq!(RoundOutcome::from_str(outcome.key()).unwrap(), *outcome);
+        }
+        assert!(RoundOutcome::from_str("victory").is_err());
+    }
 }
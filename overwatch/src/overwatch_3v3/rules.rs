@@ -0,0 +1,208 @@
+use super::Player;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Fail)]
+#[fail(display = "could not parse game rule from '{}'", _0)]
+pub struct ParseRuleError(String);
+
+/// The result of a whole match, from our point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MatchOutcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+/// Which heroes can no longer be picked once they have been played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeroLock {
+    /// Any hero can be played every round.
+    Nothing,
+    /// Heroes from rounds we won are locked for the whole team (3v3 Lockout).
+    Winners,
+    /// Every hero we have played is locked for the whole team.
+    AllPlayed,
+    /// Each player is locked out of the heroes they have played, teammates are not.
+    PerPlayer,
+}
+
+/// What happens when `max_rounds` have been played without a winner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TieBreak {
+    /// The match ends in a draw.
+    Draw,
+    /// Keep playing until one team is ahead.
+    SuddenDeath,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameRules {
+    pub rounds_to_win: usize,
+    pub max_rounds: usize,
+    pub hero_lock: HeroLock,
+    pub tie_break: TieBreak,
+}
+
+impl Default for GameRules {
+    fn default() -> GameRules {
+        GameRules::lockout()
+    }
+}
+
+impl GameRules {
+    /// 3v3 Lockout Elimination: first to three, and a hero can only win once.
+    pub fn lockout() -> GameRules {
+        GameRules {
+            rounds_to_win: 3,
+            max_rounds: 5,
+            hero_lock: HeroLock::Winners,
+            tie_break: TieBreak::Draw,
+        }
+    }
+
+    /// Plain Elimination: first to three, with no hero restrictions.
+    pub fn elimination() -> GameRules {
+        GameRules {
+            hero_lock: HeroLock::Nothing,
+            ..GameRules::lockout()
+        }
+    }
+
+    /// Decide the match given the score after `rounds` rounds, if it is over.
+    pub fn outcome(&self, rounds: usize, wins: usize, losses: usize) -> Option<MatchOutcome> {
+        if wins >= self.rounds_to_win {
+            return Some(MatchOutcome::Win);
+        } else if losses >= self.rounds_to_win {
+            return Some(MatchOutcome::Loss);
+        }
+        if rounds < self.max_rounds {
+            return None;
+        }
+        if wins > losses {
+            Some(MatchOutcome::Win)
+        } else if losses > wins {
+            Some(MatchOutcome::Loss)
+        } else {
+            match self.tie_break {
+                TieBreak::Draw => Some(MatchOutcome::Draw),
+                TieBreak::SuddenDeath => None,
+            }
+        }
+    }
+
+    /// Whether a hero `played_by` someone in a round their team `won` is locked for
+    /// `player` on the same team in later rounds.
+    pub(crate) fn locks(&self, won: bool, player: Player, played_by: Player) -> bool {
+        match self.hero_lock {
+            HeroLock::Nothing => false,
+            HeroLock::Winners => won,
+            HeroLock::AllPlayed => true,
+            HeroLock::PerPlayer => player == played_by,
+        }
+    }
+}
+
+impl HeroLock {
+    /// The name used for database entries.
+    pub fn key(self) -> &'static str {
+        match self {
+            HeroLock::Nothing => "nothing",
+            HeroLock::Winners => "winners",
+            HeroLock::AllPlayed => "all-played",
+            HeroLock::PerPlayer => "per-player",
+        }
+    }
+}
+
+impl FromStr for HeroLock {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "nothing" => HeroLock::Nothing,
+            "winners" => HeroLock::Winners,
+            "all-played" => HeroLock::AllPlayed,
+            "per-player" => HeroLock::PerPlayer,
+            s => return Err(ParseRuleError(s.into())),
+        })
+    }
+}
+
+impl TieBreak {
+    /// The name used for database entries.
+    pub fn key(self) -> &'static str {
+        match self {
+            TieBreak::Draw => "draw",
+            TieBreak::SuddenDeath => "sudden-death",
+        }
+    }
+}
+
+impl FromStr for TieBreak {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "draw" => TieBreak::Draw,
+            "sudden-death" => TieBreak::SuddenDeath,
+            s => return Err(ParseRuleError(s.into())),
+        })
+    }
+}
+
+impl fmt::Display for GameRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "first to {} (max {} rounds), {} locked",
+            self.rounds_to_win,
+            self.max_rounds,
+            self.hero_lock.key()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameRules, HeroLock, MatchOutcome, TieBreak};
+    use std::str::FromStr;
+
+    #[test]
+    fn first_to_three() {
+        let rules = GameRules::lockout();
+        assert_eq!(rules.outcome(3, 2, 1), None);
+        assert_eq!(rules.outcome(4, 3, 1), Some(MatchOutcome::Win));
+        assert_eq!(rules.outcome(5, 2, 3), Some(MatchOutcome::Loss));
+    }
+
+    #[test]
+    fn tie_after_max_rounds() {
+        let mut rules = GameRules {
+            rounds_to_win: 3,
+            max_rounds: 4,
+            hero_lock: HeroLock::Winners,
+            tie_break: TieBreak::Draw,
+        };
+        assert_eq!(rules.outcome(4, 2, 2), Some(MatchOutcome::Draw));
+
+        rules.tie_break = TieBreak::SuddenDeath;
+        assert_eq!(rules.outcome(4, 2, 2), None);
+        assert_eq!(rules.outcome(5, 2, 3), Some(MatchOutcome::Loss));
+    }
+
+    #[test]
+    fn parse_rule_keys() {
+        for lock in &[
+            HeroLock::Nothing,
+            HeroLock::Winners,
+            HeroLock::AllPlayed,
+            HeroLock::PerPlayer,
+        ] {
+            assert_eq!(HeroLock::from_str(lock.key()).unwrap(), *lock);
+        }
+        for tie in &[TieBreak::Draw, TieBreak::SuddenDeath] {
+            assert_eq!(TieBreak::from_str(tie.key()).unwrap(), *tie);
+        }
+    }
+}
@@ -4,10 +4,8 @@
 # foolproof, but this is good-enough for now
 
 
-HEROS="
-dva orisa reinhardt roadhog echo sigma winston wrecking-ball zarya ashe bastion doomfist genji
-hanzo junkrat mccree mei pharah reaper soldier-76 sombra symmetra torbjorn tracer widowmaker
-ana baptiste brigitte lucio mercy moira zenyatta"
+# Every hero's asset name, from the bundled hero data
+HEROS=$(sed -n 's/^asset = "\(.*\)"$/\1/p' ../overwatch/data/heroes.toml)
 
 OUTPUT=../assets/images/overwatch/portraits
 